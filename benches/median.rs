@@ -0,0 +1,48 @@
+// Times detection with the median statistic, which maintains sliding quantiles
+// Run with: cargo bench
+
+use std::time::Instant;
+
+fn generate_series(n: usize) -> Vec<f64> {
+    let mut seed: u64 = 1;
+    (0..n)
+        .map(|i| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let noise = (seed >> 11) as f64 / (1u64 << 53) as f64;
+            noise + (i / (n / 4)) as f64
+        })
+        .collect()
+}
+
+fn bench<F: FnMut()>(name: &str, iterations: u32, mut f: F) {
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let elapsed = start.elapsed() / iterations;
+    println!("{:<28} {:>10.2?}", name, elapsed);
+}
+
+fn main() {
+    for n in [500, 1000, 2000] {
+        let series = generate_series(n);
+        bench(&format!("multi median n={}", n), 5, || {
+            breakout::multi().min_size(30).fit(&series).unwrap();
+        });
+    }
+
+    let series = generate_series(2000);
+    bench("multi quantile n=2000", 5, || {
+        breakout::multi().quantile(0.9).fit(&series).unwrap();
+    });
+    bench("multi scale n=2000", 5, || {
+        breakout::multi()
+            .target(breakout::Target::Scale)
+            .fit(&series)
+            .unwrap();
+    });
+    bench("amoc median n=2000", 5, || {
+        breakout::amoc().fit(&series).unwrap();
+    });
+}
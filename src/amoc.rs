@@ -1,10 +1,32 @@
-use crate::Error;
+use crate::bootstrap::{interval, Rng};
+use crate::change::changes;
+use crate::divergence::Divergence;
+use crate::edm_tail::edm_tail;
+use crate::edmx::{edmx, edmx_from};
+use crate::pair::{derive, PairAnalysis};
+use crate::preprocess::{scale, Preprocess};
+use crate::statistic::with_divergence;
+use crate::{
+    Change, Direction, Error, Kernel, MinChange, Prefilter, Recent, Relation, Statistic, Target,
+    Trend,
+};
 
 /// Parameters for detecting a single breakout (at most one change).
 pub struct AmocParams {
     min_size: usize,
     alpha: f64,
     exact: bool,
+    statistic: Statistic,
+    quantile: f64,
+    target: Target,
+    kernel: Kernel,
+    bandwidth: Option<f64>,
+    direction: Option<Direction>,
+    confidence: Option<f64>,
+    samples: usize,
+    seed: u64,
+    min_change: Option<MinChange>,
+    preprocess: Preprocess,
 }
 
 /// Returns parameters for detecting a single breakout (at most one change).
@@ -13,6 +35,79 @@ pub fn amoc() -> AmocParams {
         min_size: 30,
         alpha: 2.0,
         exact: true,
+        statistic: Statistic::Median,
+        quantile: 0.5,
+        target: Target::Location,
+        kernel: Kernel::Gaussian,
+        bandwidth: None,
+        direction: None,
+        confidence: None,
+        samples: 200,
+        seed: 0,
+        min_change: None,
+        preprocess: Preprocess::new(),
+    }
+}
+
+/// The result of detecting a single breakout.
+#[derive(Debug)]
+pub struct AmocAnalysis {
+    breakout: Option<usize>,
+    change: Option<Change>,
+    interval: Option<(usize, usize)>,
+    filtered: Vec<usize>,
+}
+
+impl AmocAnalysis {
+    /// Returns the breakout.
+    pub fn breakout(&self) -> Option<usize> {
+        self.breakout
+    }
+
+    /// Returns the change in median at the breakout.
+    pub fn change(&self) -> Option<&Change> {
+        self.change.as_ref()
+    }
+
+    /// Returns the confidence interval for the location of the breakout.
+    pub fn interval(&self) -> Option<(usize, usize)> {
+        self.interval
+    }
+
+    /// Returns the indices of observations modified by the prefilter.
+    pub fn filtered(&self) -> &[usize] {
+        &self.filtered
+    }
+}
+
+/// The result of detecting a breakout in a window.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanWindow {
+    start: usize,
+    end: usize,
+    breakout: Option<usize>,
+    statistic: f64,
+}
+
+impl ScanWindow {
+    /// Returns the start of the window.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the end of the window (exclusive).
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the breakout, as an index of the series.
+    pub fn breakout(&self) -> Option<usize> {
+        self.breakout
+    }
+
+    /// Returns the statistic for the breakout.
+    pub fn statistic(&self) -> f64 {
+        self.statistic
     }
 }
 
@@ -35,47 +130,384 @@ impl AmocParams {
         self
     }
 
+    /// Sets the statistic used to compare segments.
+    ///
+    /// The mean and kernel statistics are always exact.
+    pub fn statistic(&mut self, value: Statistic) -> &mut Self {
+        self.statistic = value;
+        self
+    }
+
+    /// Sets the quantile to compare between segments when using the median statistic.
+    pub fn quantile(&mut self, value: f64) -> &mut Self {
+        self.quantile = value;
+        self
+    }
+
+    /// Sets whether to detect changes in level or spread.
+    pub fn target(&mut self, value: Target) -> &mut Self {
+        self.target = value;
+        self
+    }
+
+    /// Sets the kernel when using the kernel statistic.
+    pub fn kernel(&mut self, value: Kernel) -> &mut Self {
+        self.kernel = value;
+        self
+    }
+
+    /// Sets the bandwidth of the kernel, in the units of the series.
+    ///
+    /// Defaults to the median distance between observations.
+    pub fn bandwidth<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<f64>>,
+    {
+        self.bandwidth = value.into();
+        self
+    }
+
+    /// Sets the direction of breakouts to report.
+    pub fn direction<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<Direction>>,
+    {
+        self.direction = value.into();
+        self
+    }
+
+    /// Sets the minimum change in median for a breakout, in the units of the series.
+    pub fn min_change<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<MinChange>>,
+    {
+        self.min_change = value.into();
+        self
+    }
+
+    /// Sets the level of bootstrap confidence intervals for breakout locations.
+    pub fn confidence<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<f64>>,
+    {
+        self.confidence = value.into();
+        self
+    }
+
+    /// Sets the number of bootstrap samples for confidence intervals.
+    pub fn samples(&mut self, value: usize) -> &mut Self {
+        self.samples = value;
+        self
+    }
+
+    /// Sets the seed for bootstrap samples.
+    pub fn seed(&mut self, value: u64) -> &mut Self {
+        self.seed = value;
+        self
+    }
+
+    /// Sets the filter for outliers applied before detection.
+    pub fn prefilter<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<Prefilter>>,
+    {
+        self.preprocess.prefilter = value.into();
+        self
+    }
+
+    /// Sets the period of the seasonality to remove before detection.
+    pub fn seasonality<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<usize>>,
+    {
+        self.preprocess.seasonality = value.into();
+        self
+    }
+
+    /// Sets the method for removing a linear trend before detection.
+    pub fn detrend<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<Trend>>,
+    {
+        self.preprocess.detrend = value.into();
+        self
+    }
+
+    /// Sets whether to remove autocorrelation before detection.
+    ///
+    /// Uses an autoregressive model with the order selected by AIC.
+    pub fn prewhiten(&mut self, value: bool) -> &mut Self {
+        self.preprocess.prewhiten = value;
+        self
+    }
+
+    /// Sets whether to replace observations with their ranks before detection.
+    ///
+    /// Ties get the average of their ranks. Breakouts don't change with monotone
+    /// transformations like `ln` or `sqrt`, but the bandwidth is in units of ranks.
+    pub fn rank(&mut self, value: bool) -> &mut Self {
+        self.preprocess.rank = value;
+        self
+    }
+
     /// Detects a single breakout (at most one change).
     pub fn fit(&self, z: &[f64]) -> Result<Option<usize>, Error> {
-        if self.min_size < 2 {
-            return Err(Error::Parameter("min_size must be at least 2".to_string()));
+        Ok(self.analyze(z)?.breakout)
+    }
+
+    /// Detects a single breakout (at most one change) and returns details.
+    pub fn analyze(&self, z: &[f64]) -> Result<AmocAnalysis, Error> {
+        self.validate()?;
+
+        let mut analysis = AmocAnalysis {
+            breakout: None,
+            change: None,
+            interval: None,
+            filtered: Vec::new(),
+        };
+
+        if z.len() < self.min_size {
+            return Ok(analysis);
         }
-        if self.alpha < 0.0 || self.alpha > 2.0 {
+
+        let prepared = self.preprocess.apply(z, self.min_size)?;
+        analysis.filtered = prepared.filtered;
+
+        // scale observations
+        let (zcounts, denom) = match scale(&prepared.z) {
+            Some(scaled) => scaled,
+            None => return Ok(analysis),
+        };
+
+        let n = zcounts.len();
+        let (loc, stat) = if self.statistic == Statistic::Median && !self.exact {
+            edm_tail(&zcounts, self.min_size, self.alpha, 0)
+        } else {
+            with_divergence!(self, &zcounts, denom, |d| edmx(0, n, &mut d, self.min_size))
+        };
+
+        if stat > 0.0 {
+            // median is in the units of the original series
+            let change = changes(z, &[loc]).remove(0);
+            if self.accept(&change) {
+                analysis.breakout = Some(loc);
+                analysis.change = Some(change);
+                if let Some(level) = self.confidence {
+                    analysis.interval = interval(
+                        &zcounts,
+                        (0, loc, n),
+                        self.min_size,
+                        self.samples,
+                        level,
+                        &mut Rng::new(self.seed),
+                        |x| self.divergence(x, denom),
+                    );
+                }
+            }
+        }
+        Ok(analysis)
+    }
+
+    /// Detects a breakout in the relationship between two aligned series.
+    ///
+    /// Observations are dropped when either value is missing or the denominator is near zero.
+    pub fn analyze_pair(
+        &self,
+        a: &[f64],
+        b: &[f64],
+        relation: Relation,
+    ) -> Result<PairAnalysis, Error> {
+        let (z, kept) = derive(a, b, relation)?;
+        let breakouts: Vec<usize> = self.fit(&z)?.into_iter().collect();
+        Ok(PairAnalysis::new(a, b, &kept, &breakouts))
+    }
+
+    /// Detects a breakout in each sliding window of a series.
+    ///
+    /// Windows are scaled together, so statistics can be compared between windows.
+    pub fn scan(&self, z: &[f64], width: usize, step: usize) -> Result<Vec<ScanWindow>, Error> {
+        self.validate()?;
+        if width < 2 * self.min_size {
             return Err(Error::Parameter(
-                "alpha must be between 0 and 2".to_string(),
+                "width must be at least twice min_size".to_string(),
             ));
         }
+        if step == 0 {
+            return Err(Error::Parameter("step must be positive".to_string()));
+        }
 
-        if z.len() < self.min_size {
-            return Ok(None);
+        if z.len() < width {
+            return Ok(Vec::new());
         }
 
+        let prepared = self.preprocess.apply(z, self.min_size)?;
+        let n = z.len();
+
         // scale observations
-        let min = z.iter().min_by(|i, j| i.partial_cmp(j).unwrap()).unwrap();
-        let max = z.iter().max_by(|i, j| i.partial_cmp(j).unwrap()).unwrap();
-        let denom = max - min;
-        if denom == 0.0 {
+        let (zcounts, denom) = match scale(&prepared.z) {
+            Some(scaled) => scaled,
+            None => {
+                return Ok((0..n - width + 1)
+                    .step_by(step)
+                    .map(|start| ScanWindow {
+                        start,
+                        end: start + width,
+                        breakout: None,
+                        statistic: 0.0,
+                    })
+                    .collect())
+            }
+        };
+
+        // the divergence is shared so overlapping windows update it incrementally
+        let approximate = self.statistic == Statistic::Median && !self.exact;
+        let windows = with_divergence!(self, &zcounts, denom, |d| {
+            let mut windows = Vec::new();
+            for start in (0..n - width + 1).step_by(step) {
+                let end = start + width;
+                let (loc, stat) = if approximate {
+                    let (loc, stat) = edm_tail(&zcounts[start..end], self.min_size, self.alpha, 0);
+                    (start + loc, stat)
+                } else {
+                    edmx(start, end, &mut d, self.min_size)
+                };
+
+                let mut breakout = None;
+                if stat > 0.0 {
+                    let change = changes(&z[start..end], &[loc - start]).remove(0);
+                    if self.accept(&change) {
+                        breakout = Some(loc);
+                    }
+                }
+                windows.push(ScanWindow {
+                    start,
+                    end,
+                    breakout,
+                    statistic: stat,
+                });
+            }
+            windows
+        });
+        Ok(windows)
+    }
+
+    /// Detects a breakout in the last `k` observations.
+    ///
+    /// Only breakouts in the last `k` observations are considered, which is faster than `fit`.
+    pub fn recent(&self, z: &[f64], k: usize) -> Result<Option<Recent>, Error> {
+        self.validate()?;
+        if k < self.min_size {
+            return Err(Error::Parameter("k must be at least min_size".to_string()));
+        }
+
+        if z.len() < self.min_size {
             return Ok(None);
         }
-        let zcounts: Vec<f64> = z.iter().map(|x| (x - min) / denom).collect();
 
-        let (loc, stat) = if self.exact {
-            crate::edmx::edmx(&zcounts, self.min_size, self.alpha)
+        let prepared = self.preprocess.apply(z, self.min_size)?;
+        let (zcounts, denom) = match scale(&prepared.z) {
+            Some(scaled) => scaled,
+            None => return Ok(None),
+        };
+
+        let n = zcounts.len();
+        let from = n.saturating_sub(k);
+        let (loc, stat) = if self.statistic == Statistic::Median && !self.exact {
+            edm_tail(&zcounts, self.min_size, self.alpha, from)
         } else {
-            crate::edm_tail::edm_tail(&zcounts, self.min_size, self.alpha)
+            with_divergence!(self, &zcounts, denom, |d| {
+                edmx_from(0, n, from, &mut d, self.min_size)
+            })
         };
 
         if stat > 0.0 {
-            Ok(Some(loc))
-        } else {
-            Ok(None)
+            let change = changes(z, &[loc]).remove(0);
+            if self.accept(&change) {
+                return Ok(Some(Recent::new(loc, stat)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.min_size < 2 {
+            return Err(Error::Parameter("min_size must be at least 2".to_string()));
+        }
+        if self.alpha < 0.0 || self.alpha > 2.0 {
+            return Err(Error::Parameter(
+                "alpha must be between 0 and 2".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.quantile) {
+            return Err(Error::Parameter(
+                "quantile must be between 0 and 1".to_string(),
+            ));
+        }
+        if self.quantile != 0.5 {
+            if self.statistic != Statistic::Median {
+                return Err(Error::Parameter(
+                    "quantile requires median statistic".to_string(),
+                ));
+            }
+            if !self.exact {
+                return Err(Error::Parameter(
+                    "quantile requires exact median".to_string(),
+                ));
+            }
+            if self.target == Target::Scale {
+                return Err(Error::Parameter(
+                    "quantile requires location target".to_string(),
+                ));
+            }
+        }
+        if self.target == Target::Scale && self.statistic == Statistic::Median && !self.exact {
+            return Err(Error::Parameter(
+                "scale target requires exact median".to_string(),
+            ));
+        }
+        if self.target == Target::Scale && self.statistic == Statistic::Kernel {
+            return Err(Error::Parameter(
+                "scale target requires median or mean statistic".to_string(),
+            ));
+        }
+        if let Some(bandwidth) = self.bandwidth {
+            if !(bandwidth.is_finite() && bandwidth > 0.0) {
+                return Err(Error::Parameter("bandwidth must be positive".to_string()));
+            }
+        }
+        if let Some(min_change) = &self.min_change {
+            min_change.validate()?;
+        }
+        if let Some(confidence) = self.confidence {
+            if confidence <= 0.0 || confidence >= 1.0 {
+                return Err(Error::Parameter(
+                    "confidence must be between 0 and 1".to_string(),
+                ));
+            }
+            if self.samples == 0 {
+                return Err(Error::Parameter("samples must be positive".to_string()));
+            }
         }
+        self.preprocess.validate()?;
+        Ok(())
+    }
+
+    // Returns whether a change passes the minimum change and direction
+    fn accept(&self, change: &Change) -> bool {
+        self.min_change.map_or(true, |m| m.satisfied(change))
+            && (self.direction.is_none() || change.direction() == self.direction)
+    }
+
+    // Returns the divergence for the statistic and target of a scaled series
+    // Boxed for bootstrap samples, which don't need static dispatch
+    fn divergence<'a>(&self, z: &'a [f64], denom: f64) -> Box<dyn Divergence + 'a> {
+        with_divergence!(self, z, denom, |d| Box::new(d) as Box<dyn Divergence + 'a>)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Error;
+    use crate::{Direction, Error, MinChange, Prefilter, Statistic, Target, Trend};
 
     #[rustfmt::skip]
     fn generate_series() -> Vec<f64> {
@@ -93,6 +525,103 @@ mod tests {
         assert_eq!(breakout, Some(19));
     }
 
+    #[test]
+    fn test_change() {
+        let series = generate_series();
+        let analysis = crate::amoc().min_size(5).analyze(&series).unwrap();
+        let change = analysis.change().unwrap();
+        assert_eq!(19, change.index());
+        assert_eq!(3.0, change.before());
+        assert_eq!(9.0, change.after());
+        assert_eq!(Some(Direction::Increase), change.direction());
+    }
+
+    #[test]
+    fn test_direction() {
+        let series = generate_series();
+        let breakout = crate::amoc()
+            .min_size(5)
+            .direction(Direction::Decrease)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(breakout, None);
+    }
+
+    #[test]
+    fn test_min_change() {
+        let series = generate_series();
+        let breakout = crate::amoc()
+            .min_size(5)
+            .min_change(MinChange::Absolute(7.0))
+            .fit(&series)
+            .unwrap();
+        assert_eq!(breakout, None);
+
+        let breakout = crate::amoc()
+            .min_size(5)
+            .min_change(MinChange::Relative(1.5))
+            .fit(&series)
+            .unwrap();
+        assert_eq!(breakout, Some(19));
+    }
+
+    #[test]
+    fn test_confidence() {
+        let series = generate_series();
+        let analysis = crate::amoc()
+            .min_size(5)
+            .confidence(0.9)
+            .analyze(&series)
+            .unwrap();
+        assert_eq!(Some((15, 21)), analysis.interval());
+    }
+
+    #[test]
+    fn test_scan() {
+        let series = generate_series();
+        let windows = crate::amoc().min_size(5).scan(&series, 20, 5).unwrap();
+        assert_eq!(windows.len(), 3);
+        for window in windows {
+            let expected = crate::amoc()
+                .min_size(5)
+                .fit(&series[window.start()..window.end()])
+                .unwrap()
+                .map(|b| window.start() + b);
+            assert_eq!(window.breakout(), expected);
+            assert!(window.statistic() > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_scan_short() {
+        let series = generate_series();
+        let windows = crate::amoc()
+            .min_size(5)
+            .scan(&series[..10], 20, 5)
+            .unwrap();
+        assert!(windows.is_empty());
+    }
+
+    #[test]
+    fn test_recent() {
+        let series = generate_series();
+        let recent = crate::amoc()
+            .min_size(5)
+            .recent(&series, 12)
+            .unwrap()
+            .unwrap();
+        assert_eq!(19, recent.index());
+        assert!(recent.statistic() > 0.0);
+
+        let recent = crate::amoc()
+            .min_size(5)
+            .exact(false)
+            .recent(&series, 12)
+            .unwrap()
+            .unwrap();
+        assert_eq!(20, recent.index());
+    }
+
     #[test]
     fn test_tail() {
         let series = generate_series();
@@ -100,6 +629,137 @@ mod tests {
         assert_eq!(breakout, Some(20));
     }
 
+    #[test]
+    fn test_mean() {
+        let series = generate_series();
+        let breakout = crate::amoc()
+            .min_size(5)
+            .statistic(Statistic::Mean)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(breakout, Some(20));
+    }
+
+    #[test]
+    fn test_quantile() {
+        let series = generate_series();
+        let breakout = crate::amoc()
+            .min_size(5)
+            .quantile(0.9)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(breakout, Some(11));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_scale() {
+        let series = vec![
+            5.0, 5.1, 4.9, 5.0, 5.1, 4.9, 5.0, 5.1, 4.9, 5.0,
+            8.0, 2.0, 7.0, 3.0, 9.0, 1.0, 6.0, 4.0, 0.0, 10.0
+        ];
+        let breakout = crate::amoc().min_size(8).target(Target::Scale).fit(&series).unwrap();
+        assert_eq!(breakout, Some(10));
+    }
+
+    #[test]
+    fn test_kernel() {
+        let series = generate_series();
+        let breakout = crate::amoc()
+            .min_size(5)
+            .statistic(Statistic::Kernel)
+            .bandwidth(2.0)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(breakout, Some(20));
+    }
+
+    #[test]
+    fn test_seasonality() {
+        let pattern = [0.0, 2.0, 4.0, 6.0, 4.0, 2.0, 0.0];
+        let series: Vec<f64> = (0..70)
+            .map(|i| pattern[i % 7] + if i < 35 { 0.0 } else { 3.0 })
+            .collect();
+        let breakout = crate::amoc()
+            .min_size(10)
+            .seasonality(7)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(breakout, Some(35));
+    }
+
+    #[test]
+    fn test_seasonality_change() {
+        let pattern = [0.0, 2.0, 4.0, 6.0, 4.0, 2.0, 0.0];
+        let series: Vec<f64> = (0..70)
+            .map(|i| pattern[i % 7] + if i < 35 { 0.0 } else { 3.0 })
+            .collect();
+        let analysis = crate::amoc()
+            .min_size(10)
+            .seasonality(7)
+            .analyze(&series)
+            .unwrap();
+        let change = analysis.change().unwrap();
+        assert_eq!(2.0, change.before());
+        assert_eq!(5.0, change.after());
+    }
+
+    #[test]
+    fn test_detrend() {
+        let series: Vec<f64> = (0..60)
+            .map(|i| 0.2 * i as f64 + if i < 30 { 0.0 } else { 5.0 })
+            .collect();
+        let breakout = crate::amoc()
+            .min_size(10)
+            .detrend(Trend::Linear)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(breakout, Some(30));
+    }
+
+    #[test]
+    fn test_rank() {
+        let series = generate_series();
+        let transformed: Vec<f64> = series.iter().map(|v| v.exp()).collect();
+        for (exact, expected) in [(true, Some(19)), (false, Some(9))] {
+            let mut params = crate::amoc();
+            params.min_size(5).exact(exact).rank(true);
+            assert_eq!(expected, params.fit(&series).unwrap());
+            assert_eq!(expected, params.fit(&transformed).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_prefilter() {
+        let mut series = generate_series();
+        series[25] = 100.0;
+        let analysis = crate::amoc()
+            .min_size(5)
+            .prefilter(Prefilter::Winsorize {
+                lower: 0.0,
+                upper: 0.95,
+            })
+            .analyze(&series)
+            .unwrap();
+        assert_eq!(analysis.breakout(), Some(19));
+        assert_eq!(analysis.filtered(), vec![25]);
+    }
+
+    #[test]
+    fn test_bad_prefilter() {
+        let series = Vec::new();
+        let result = crate::amoc()
+            .prefilter(Prefilter::Hampel {
+                window: 1,
+                threshold: 3.0,
+            })
+            .fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("window must be at least 3".to_string())
+        );
+    }
+
     #[test]
     fn test_empty() {
         let series = Vec::new();
@@ -133,6 +793,16 @@ mod tests {
         assert_eq!(breakout, Some(10));
     }
 
+    #[test]
+    fn test_bad_width() {
+        let series = generate_series();
+        let result = crate::amoc().min_size(5).scan(&series, 9, 1);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("width must be at least twice min_size".to_string())
+        );
+    }
+
     #[test]
     fn test_bad_min_size() {
         let series = Vec::new();
@@ -152,4 +822,66 @@ mod tests {
             Error::Parameter("alpha must be between 0 and 2".to_string())
         );
     }
+
+    #[test]
+    fn test_bad_quantile() {
+        let series = Vec::new();
+        let result = crate::amoc().quantile(-0.5).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("quantile must be between 0 and 1".to_string())
+        );
+
+        let result = crate::amoc().quantile(f64::NAN).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("quantile must be between 0 and 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_bandwidth() {
+        let series = Vec::new();
+        let result = crate::amoc().bandwidth(f64::NAN).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("bandwidth must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_quantile_tail() {
+        let series = Vec::new();
+        let result = crate::amoc().quantile(0.9).exact(false).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("quantile requires exact median".to_string())
+        );
+    }
+
+    #[test]
+    fn test_scale_tail() {
+        let series = Vec::new();
+        let result = crate::amoc()
+            .target(Target::Scale)
+            .exact(false)
+            .fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("scale target requires exact median".to_string())
+        );
+    }
+
+    #[test]
+    fn test_scale_kernel() {
+        let series = Vec::new();
+        let result = crate::amoc()
+            .target(Target::Scale)
+            .statistic(Statistic::Kernel)
+            .fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("scale target requires median or mean statistic".to_string())
+        );
+    }
 }
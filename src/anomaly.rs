@@ -0,0 +1,35 @@
+use crate::quantile::{mad, median};
+
+// Returns observations more than threshold robust deviations from the median of their segment
+pub fn anomalies(z: &[f64], breakouts: &[usize], threshold: f64) -> Vec<usize> {
+    let mut ret = Vec::new();
+    let mut start = 0;
+    for end in breakouts.iter().copied().chain(std::iter::once(z.len())) {
+        let segment = &z[start..end];
+        let m = median(segment);
+        let limit = threshold * mad(segment, m);
+        for (i, v) in segment.iter().enumerate() {
+            if (v - m).abs() > limit {
+                ret.push(start + i);
+            }
+        }
+        start = end;
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::anomaly::anomalies;
+
+    #[test]
+    fn test_anomalies() {
+        let mut series = vec![
+            1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 11.0, 12.0, 11.0, 12.0, 11.0, 12.0,
+        ];
+        series[2] = 8.0;
+        series[9] = 0.0;
+        assert_eq!(anomalies(&series, &[6], 3.0), vec![2, 9]);
+        assert_eq!(anomalies(&series, &[6], 100.0), Vec::<usize>::new());
+    }
+}
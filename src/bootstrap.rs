@@ -0,0 +1,123 @@
+// Moving block bootstrap for the location of a breakout
+// Each segment is resampled separately with blocks of consecutive observations,
+// which keeps short-range dependence, and the breakout is located again
+
+use crate::divergence::Divergence;
+use crate::edmx::edmx;
+use crate::quantile::quantile;
+
+// Small deterministic generator (SplitMix64), so results are reproducible with a seed
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Returns a number in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// Appends a resample of x with blocks of about the cube root of its length
+fn resample(x: &[f64], rng: &mut Rng, out: &mut Vec<f64>) {
+    let n = x.len();
+    let block = ((n as f64).cbrt().ceil() as usize).clamp(1, n);
+    let end = out.len() + n;
+    while out.len() < end {
+        let start = rng.below(n - block + 1);
+        let take = block.min(end - out.len());
+        out.extend_from_slice(&x[start..start + take]);
+    }
+}
+
+// Returns the confidence interval for a breakout at t in z[start..end]
+// Returns None when a neighboring segment has only min_size observations, since the
+// breakout can't move toward it, and the interval would only reflect the constraint
+pub fn interval<F>(
+    z: &[f64],
+    (start, t, end): (usize, usize, usize),
+    min_size: usize,
+    samples: usize,
+    level: f64,
+    rng: &mut Rng,
+    divergence: F,
+) -> Option<(usize, usize)>
+where
+    F: for<'a> Fn(&'a [f64]) -> Box<dyn Divergence + 'a>,
+{
+    if t - start <= min_size || end - t <= min_size {
+        return None;
+    }
+
+    let mut locations = Vec::with_capacity(samples);
+    let mut x = Vec::with_capacity(end - start);
+    for _ in 0..samples {
+        x.clear();
+        resample(&z[start..t], rng, &mut x);
+        resample(&z[t..end], rng, &mut x);
+        let (loc, _) = edmx(0, x.len(), divergence(&x).as_mut(), min_size);
+        locations.push((start + loc) as f64);
+    }
+
+    let tail = (1.0 - level) / 2.0;
+    let lower = quantile(&locations, tail).floor() as usize;
+    let upper = quantile(&locations, 1.0 - tail).ceil() as usize;
+    Some((lower, upper))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bootstrap::{interval, resample, Rng};
+    use crate::divergence::Means;
+
+    #[test]
+    fn test_resample() {
+        let x: Vec<f64> = (0..27).map(|v| v as f64).collect();
+        let mut rng = Rng::new(1);
+        let mut out = vec![-1.0];
+        resample(&x, &mut rng, &mut out);
+        assert_eq!(out.len(), 28);
+        // blocks of three consecutive observations
+        for i in (1..28).step_by(3) {
+            assert_eq!(out[i + 1], out[i] + 1.0);
+            assert_eq!(out[i + 2], out[i] + 2.0);
+        }
+    }
+
+    #[test]
+    fn test_interval() {
+        let mut rng = Rng::new(42);
+        let z: Vec<f64> = (0..40)
+            .map(|i| {
+                let noise = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+                if i < 20 {
+                    noise
+                } else {
+                    5.0 + noise
+                }
+            })
+            .collect();
+        let result = interval(&z, (0, 20, 40), 5, 50, 0.9, &mut rng, |x| {
+            Box::new(Means::new(x))
+        });
+        assert_eq!(result, Some((20, 20)));
+
+        // the breakout can't move toward a segment with min_size observations
+        let result = interval(&z, (15, 20, 40), 5, 50, 0.9, &mut rng, |x| {
+            Box::new(Means::new(x))
+        });
+        assert_eq!(result, None);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::quantile::median;
+
+/// The direction of a breakout.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// The median increases.
+    Increase,
+    /// The median decreases.
+    Decrease,
+}
+
+/// The change in median at a breakout, in the units of the series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    index: usize,
+    before: f64,
+    after: f64,
+}
+
+impl Change {
+    /// Returns the index of the breakout.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the median of the segment before the breakout.
+    pub fn before(&self) -> f64 {
+        self.before
+    }
+
+    /// Returns the median of the segment after the breakout.
+    pub fn after(&self) -> f64 {
+        self.after
+    }
+
+    /// Returns the direction of the change, or `None` if the medians are equal.
+    pub fn direction(&self) -> Option<Direction> {
+        if self.after > self.before {
+            Some(Direction::Increase)
+        } else if self.after < self.before {
+            Some(Direction::Decrease)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the absolute change.
+    pub fn absolute(&self) -> f64 {
+        self.after - self.before
+    }
+
+    /// Returns the change relative to the median before the breakout, or `None` if it is zero.
+    pub fn relative(&self) -> Option<f64> {
+        if self.before == 0.0 {
+            None
+        } else {
+            Some(self.absolute() / self.before.abs())
+        }
+    }
+}
+
+// Returns the change between the medians of adjacent segments
+pub fn changes(z: &[f64], breakouts: &[usize]) -> Vec<Change> {
+    let mut bounds = vec![0];
+    bounds.extend_from_slice(breakouts);
+    bounds.push(z.len());
+    let medians: Vec<f64> = bounds.windows(2).map(|w| median(&z[w[0]..w[1]])).collect();
+    breakouts
+        .iter()
+        .enumerate()
+        .map(|(i, index)| Change {
+            index: *index,
+            before: medians[i],
+            after: medians[i + 1],
+        })
+        .collect()
+}
+
+// Removes breakouts that fail a test of the segments before and after, merging them
+// The failing change with the smallest strength is removed first, since medians change
+// after each merge
+pub fn merge<F, G>(z: &[f64], breakouts: &[usize], keep: F, strength: G) -> Vec<usize>
+where
+    F: Fn(&[f64], &[f64], &Change) -> bool,
+    G: Fn(&[f64], &[f64], &Change) -> f64,
+{
+    let mut breakouts = breakouts.to_vec();
+    loop {
+        let mut weakest: Option<(usize, f64)> = None;
+        for (i, change) in changes(z, &breakouts).iter().enumerate() {
+            let start = if i == 0 { 0 } else { breakouts[i - 1] };
+            let end = breakouts.get(i + 1).copied().unwrap_or(z.len());
+            let (x, y) = (&z[start..change.index], &z[change.index..end]);
+            if !keep(x, y, change) {
+                let size = strength(x, y, change);
+                if weakest.map_or(true, |(_, s)| size < s) {
+                    weakest = Some((i, size));
+                }
+            }
+        }
+        match weakest {
+            Some((i, _)) => {
+                breakouts.remove(i);
+            }
+            None => return breakouts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::change::{changes, merge};
+    use crate::{Change, Direction};
+
+    #[test]
+    fn test_changes() {
+        let series = [2.0, 2.0, 2.0, 5.0, 5.0, 5.0, 1.0, 1.0];
+        let changes = changes(&series, &[3, 6]);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].index(), 3);
+        assert_eq!(changes[0].direction(), Some(Direction::Increase));
+        assert_eq!(changes[0].absolute(), 3.0);
+        assert_eq!(changes[0].relative(), Some(1.5));
+        assert_eq!(changes[1].direction(), Some(Direction::Decrease));
+        assert_eq!(changes[1].absolute(), -4.0);
+        assert_eq!(changes[1].relative(), Some(-0.8));
+    }
+
+    #[test]
+    fn test_merge() {
+        let series = [2.0, 2.0, 2.0, 2.5, 2.5, 2.5, 3.2, 3.2, 3.2, 9.0, 9.0];
+        let keep = |_: &[f64], _: &[f64], c: &Change| c.absolute().abs() >= 0.75;
+        let breakouts = merge(&series, &[3, 6, 9], keep, |_, _, c| c.absolute().abs());
+        assert_eq!(breakouts, vec![6, 9]);
+
+        // the weakest change is by strength
+        let breakouts = merge(&series, &[3, 6, 9], keep, |_, _, c| -c.absolute().abs());
+        assert_eq!(breakouts, vec![3, 9]);
+    }
+}
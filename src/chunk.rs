@@ -0,0 +1,86 @@
+// Detection for long series with overlapping chunks
+// Each chunk is segmented independently and keeps the breakouts closest to its middle,
+// then the region around each cut between chunks is segmented again with a chunk centered on it
+
+// Returns the breakouts of a series of length n
+// detect returns the breakouts in z[start..end], as indices of the series
+pub fn chunked<F>(n: usize, size: usize, mut detect: F) -> Vec<usize>
+where
+    F: FnMut(usize, usize) -> Vec<usize>,
+{
+    let overlap = size / 4;
+    let mut breakouts = Vec::new();
+    let mut cuts = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + size).min(n);
+        let lower = cuts.last().copied().unwrap_or(0);
+        let upper = if end == n { n } else { end - overlap / 2 };
+        breakouts.extend(
+            detect(start, end)
+                .into_iter()
+                .filter(|b| *b >= lower && *b < upper),
+        );
+        if end == n {
+            break;
+        }
+        cuts.push(upper);
+        start = end - overlap;
+    }
+
+    // re-examine the region around each cut
+    for cut in cuts {
+        let lo = cut - overlap / 2;
+        let hi = (cut + overlap / 2).min(n);
+        breakouts.retain(|b| *b < lo || *b >= hi);
+
+        // stop at the neighboring breakouts so segments are unchanged outside the region
+        let i = breakouts.partition_point(|b| *b < lo);
+        let start = cut
+            .saturating_sub(size / 2)
+            .max(if i > 0 { breakouts[i - 1] } else { 0 });
+        let end = (cut + size / 2).min(breakouts.get(i).copied().unwrap_or(n));
+
+        let found: Vec<usize> = detect(start, end)
+            .into_iter()
+            .filter(|b| *b >= lo && *b < hi)
+            .collect();
+        breakouts.splice(i..i, found);
+    }
+    breakouts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunk::chunked;
+
+    #[test]
+    fn test_chunked() {
+        // breakouts at multiples of 30, found by any chunk that contains them away from its edges
+        let truth = [30, 60, 90, 120, 150, 180];
+        let mut chunks = Vec::new();
+        let breakouts = chunked(200, 80, |start, end| {
+            chunks.push((start, end));
+            truth
+                .iter()
+                .copied()
+                .filter(|b| *b >= start + 5 && *b + 5 <= end)
+                .collect()
+        });
+        assert_eq!(breakouts, truth);
+        assert!(chunks.iter().all(|(start, end)| end - start <= 80));
+    }
+
+    #[test]
+    fn test_chunked_cut() {
+        // a breakout near a cut is found again by the chunk centered on it
+        let breakouts = chunked(200, 80, |start, end| {
+            if start <= 60 && end >= 80 {
+                vec![70]
+            } else {
+                Vec::new()
+            }
+        });
+        assert_eq!(breakouts, vec![70]);
+    }
+}
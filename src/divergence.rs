@@ -0,0 +1,248 @@
+// Segment statistics shared by the multi and single breakout searches
+
+// Measures how different two adjacent segments of a series are
+pub trait Divergence {
+    // Returns the divergence between z[a..t] and z[t..s]
+    fn between(&mut self, a: usize, t: usize, s: usize) -> f64;
+}
+
+// Summary of a collection of observations that supports insertion and removal
+pub trait Tracker {
+    fn insert(&mut self, x: f64);
+    fn remove(&mut self, x: f64);
+    fn clear(&mut self);
+    fn value(&self) -> f64;
+}
+
+// Tracker over a contiguous range of the series
+// Moving the range only inserts and removes the observations that changed
+pub struct Window<T> {
+    tracker: T,
+    start: usize,
+    end: usize,
+}
+
+impl<T: Tracker> Window<T> {
+    pub fn new(tracker: T) -> Self {
+        Self {
+            tracker,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    pub fn set(&mut self, z: &[f64], start: usize, end: usize) {
+        if start == self.start && end == self.end {
+            return;
+        }
+
+        let moves = self.start.abs_diff(start) + self.end.abs_diff(end);
+        if start >= self.end || end <= self.start || moves >= end - start {
+            // rebuild when the ranges are disjoint or it is cheaper
+            self.tracker.clear();
+            for i in start..end {
+                self.tracker.insert(z[i]);
+            }
+        } else {
+            // grow before shrinking so the tracker is never empty
+            for i in start..self.start {
+                self.tracker.insert(z[i]);
+            }
+            for i in self.end..end {
+                self.tracker.insert(z[i]);
+            }
+            for i in self.start..start {
+                self.tracker.remove(z[i]);
+            }
+            for i in end..self.end {
+                self.tracker.remove(z[i]);
+            }
+        }
+
+        self.start = start;
+        self.end = end;
+    }
+
+    pub fn value(&self) -> f64 {
+        self.tracker.value()
+    }
+}
+
+// Squared difference in tracked values, like quantiles or spreads
+pub struct Trackers<'a, T> {
+    z: &'a [f64],
+    left: Window<T>,
+    right: Window<T>,
+}
+
+impl<'a, T: Tracker> Trackers<'a, T> {
+    pub fn new(z: &'a [f64], left: T, right: T) -> Self {
+        Self {
+            z,
+            left: Window::new(left),
+            right: Window::new(right),
+        }
+    }
+}
+
+impl<T: Tracker> Divergence for Trackers<'_, T> {
+    fn between(&mut self, a: usize, t: usize, s: usize) -> f64 {
+        self.left.set(self.z, a, t);
+        self.right.set(self.z, t, s);
+        (self.left.value() - self.right.value()).powi(2)
+    }
+}
+
+// Squared difference in means using prefix sums
+pub struct Means {
+    sums: Vec<f64>,
+}
+
+impl Means {
+    pub fn new(z: &[f64]) -> Self {
+        let mut sums = Vec::with_capacity(z.len() + 1);
+        let mut total = 0.0;
+        sums.push(total);
+        for v in z {
+            total += v;
+            sums.push(total);
+        }
+        Self { sums }
+    }
+
+    fn mean(&self, start: usize, end: usize) -> f64 {
+        (self.sums[end] - self.sums[start]) / (end - start) as f64
+    }
+}
+
+impl Divergence for Means {
+    fn between(&mut self, a: usize, t: usize, s: usize) -> f64 {
+        (self.mean(a, t) - self.mean(t, s)).powi(2)
+    }
+}
+
+// Squared difference in standard deviations using prefix sums
+pub struct Deviations {
+    sums: Vec<f64>,
+    squares: Vec<f64>,
+}
+
+impl Deviations {
+    pub fn new(z: &[f64]) -> Self {
+        let mut sums = Vec::with_capacity(z.len() + 1);
+        let mut squares = Vec::with_capacity(z.len() + 1);
+        let mut total = 0.0;
+        let mut total2 = 0.0;
+        sums.push(total);
+        squares.push(total2);
+        for v in z {
+            total += v;
+            total2 += v * v;
+            sums.push(total);
+            squares.push(total2);
+        }
+        Self { sums, squares }
+    }
+
+    fn std_dev(&self, start: usize, end: usize) -> f64 {
+        let n = (end - start) as f64;
+        let mean = (self.sums[end] - self.sums[start]) / n;
+        let var = (self.squares[end] - self.squares[start]) / n - mean * mean;
+        // prevent negative values due to rounding
+        var.max(0.0).sqrt()
+    }
+}
+
+impl Divergence for Deviations {
+    fn between(&mut self, a: usize, t: usize, s: usize) -> f64 {
+        (self.std_dev(a, t) - self.std_dev(t, s)).powi(2)
+    }
+}
+
+// Squared maximum mean discrepancy using spectral features of a kernel
+// Uses the unbiased estimate, which excludes each observation's similarity with itself
+pub struct Features {
+    // prefix sums of cos(w x) and sin(w x) for each frequency
+    sums: Vec<f64>,
+    width: usize,
+}
+
+impl Features {
+    pub fn new(z: &[f64], frequencies: &[f64]) -> Self {
+        let width = 2 * frequencies.len();
+        let mut sums = vec![0.0; (z.len() + 1) * width];
+        for (i, v) in z.iter().enumerate() {
+            let (prev, next) = sums[i * width..(i + 2) * width].split_at_mut(width);
+            for (k, w) in frequencies.iter().enumerate() {
+                let (sin, cos) = (w * v).sin_cos();
+                next[2 * k] = prev[2 * k] + cos;
+                next[2 * k + 1] = prev[2 * k + 1] + sin;
+            }
+        }
+        Self { sums, width }
+    }
+}
+
+impl Divergence for Features {
+    fn between(&mut self, a: usize, t: usize, s: usize) -> f64 {
+        let w = self.width;
+        let mut left = 0.0;
+        let mut right = 0.0;
+        let mut cross = 0.0;
+        for k in 0..w {
+            let l = self.sums[t * w + k] - self.sums[a * w + k];
+            let r = self.sums[s * w + k] - self.sums[t * w + k];
+            left += l * l;
+            right += r * r;
+            cross += l * r;
+        }
+
+        // each frequency contributes cos^2 + sin^2 = 1 per observation
+        let d = (w / 2) as f64;
+        let nl = (t - a) as f64;
+        let nr = (s - t) as f64;
+        (left / d - nl) / (nl * (nl - 1.0)) + (right / d - nr) / (nr * (nr - 1.0))
+            - 2.0 * cross / (d * nl * nr)
+    }
+}
+
+// Log likelihood ratio of splitting a segment of counts
+// Uses a Poisson likelihood, or a negative binomial with a known dispersion
+pub struct Counts {
+    sums: Vec<f64>,
+    dispersion: Option<f64>,
+}
+
+impl Counts {
+    pub fn new(z: &[u64], dispersion: Option<f64>) -> Self {
+        let mut sums = Vec::with_capacity(z.len() + 1);
+        let mut total = 0.0;
+        sums.push(total);
+        for v in z {
+            total += *v as f64;
+            sums.push(total);
+        }
+        Self { sums, dispersion }
+    }
+
+    // maximized log likelihood of a segment, up to terms that cancel in the ratio
+    fn log_likelihood(&self, start: usize, end: usize) -> f64 {
+        let total = self.sums[end] - self.sums[start];
+        let n = (end - start) as f64;
+        let mean = total / n;
+        let ll = if total > 0.0 { total * mean.ln() } else { 0.0 };
+        match self.dispersion {
+            Some(r) => ll - (total + n * r) * (mean + r).ln(),
+            None => ll,
+        }
+    }
+}
+
+impl Divergence for Counts {
+    fn between(&mut self, a: usize, t: usize, s: usize) -> f64 {
+        let ratio =
+            self.log_likelihood(a, t) + self.log_likelihood(t, s) - self.log_likelihood(a, s);
+        // undo the normalization so the statistic is the ratio itself
+        ratio * ((s - a) as f64).powi(2) / ((t - a) * (s - t)) as f64
+    }
+}
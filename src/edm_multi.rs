@@ -1,86 +1,4 @@
-use crate::multiset::Multiset;
-use std::cmp::Ordering;
-
-#[derive(PartialEq)]
-struct MaxItem(f64);
-
-impl Eq for MaxItem {}
-
-// opposite of edmx
-impl PartialOrd for MaxItem {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for MaxItem {
-    fn cmp(&self, other: &MaxItem) -> Ordering {
-        other.0.partial_cmp(&self.0).unwrap()
-    }
-}
-
-#[derive(PartialEq)]
-struct MinItem(f64);
-
-impl Eq for MinItem {}
-
-// opposite of edmx
-impl PartialOrd for MinItem {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for MinItem {
-    fn cmp(&self, other: &MinItem) -> Ordering {
-        self.0.partial_cmp(&other.0).unwrap()
-    }
-}
-
-fn insert_element(m: &mut Multiset<MinItem>, m2: &mut Multiset<MaxItem>, x: f64) {
-    if m.is_empty() || x < m.first().unwrap().0 {
-        m2.insert(MaxItem(x));
-    } else {
-        m.insert(MinItem(x));
-    }
-
-    if m.len() > m2.len() + 1 {
-        let i = m.first().unwrap().0;
-        m2.insert(MaxItem(i));
-        m.remove(MinItem(i));
-    } else if m2.len() > m.len() + 1 {
-        let i = m2.first().unwrap().0;
-        m.insert(MinItem(i));
-        m2.remove(MaxItem(i));
-    }
-}
-
-fn remove_element(m: &mut Multiset<MinItem>, m2: &mut Multiset<MaxItem>, x: f64) {
-    if x < m.first().unwrap().0 {
-        m2.remove(MaxItem(x));
-    } else {
-        m.remove(MinItem(x));
-    }
-
-    if m.len() > m2.len() + 1 {
-        let i = m.first().unwrap().0;
-        m2.insert(MaxItem(i));
-        m.remove(MinItem(i));
-    } else if m2.len() > m.len() + 1 {
-        let i = m2.first().unwrap().0;
-        m.insert(MinItem(i));
-        m2.remove(MaxItem(i));
-    }
-}
-
-// given a pair of trees obtain the median
-fn get_median(m: &Multiset<MinItem>, m2: &Multiset<MaxItem>) -> f64 {
-    match m.len().cmp(&m2.len()) {
-        Ordering::Greater => m.first().unwrap().0,
-        Ordering::Less => m2.first().unwrap().0,
-        Ordering::Equal => (m2.first().unwrap().0 + m.first().unwrap().0) / 2.0,
-    }
-}
+use crate::divergence::Divergence;
 
 fn linear(_x: f64) -> f64 {
     1.0
@@ -94,196 +12,210 @@ fn quadratic(x: f64) -> f64 {
     2.0 * x + 1.0
 }
 
-pub fn edm_multi(z: &[f64], min_size: usize, beta: f64, degree: i32) -> Vec<usize> {
-    // identify which type of penalization to use
-    let g: fn(f64) -> f64 = match degree {
-        1 => linear,
-        2 => quadratic,
-        _ => constant,
-    };
-
-    let n = z.len();
-    let mut beta = beta;
-    // assume that beta is a positive number
-    if beta < 0.0 {
-        beta = -beta;
-    }
-    let mut prev = vec![0; n + 1];
-    let mut number = vec![0; n + 1];
-    let mut f = vec![-3.0; n + 1];
-
-    // trees used to store the "upper half" of the considered observations
-    let mut right_min = Multiset::new();
-    let mut left_min = Multiset::new();
-
-    // trees used to store the "lower half" of the considered observations
-    let mut right_max = Multiset::new();
-    let mut left_max = Multiset::new();
-
-    // iterate over possible locations for the last change
-    for s in 2 * min_size..n + 1 {
-        right_max.clear();
-        right_min.clear();
-        left_max.clear();
-        left_min.clear();
-
-        // initialize left and right trees to account for minimum segment size
-        for i in prev[min_size - 1]..min_size - 1 {
-            insert_element(&mut left_min, &mut left_max, z[i]);
-        }
-        for i in min_size - 1..s {
-            insert_element(&mut right_min, &mut right_max, z[i]);
-        }
-
-        // iterate over possible locations for the penultimate change
-        // modify limits to deal with min_size
-        for t in min_size..s - min_size + 1 {
-            // insert element into left tree
-            insert_element(&mut left_min, &mut left_max, z[t - 1]);
-            // remove element from right tree
-            remove_element(&mut right_min, &mut right_max, z[t - 1]);
-
-            // left tree now has { Z[prev[t-1]], ..., Z[t-1] }
-            // right tree now has { Z[t], ..., Z[s-1] }
+pub fn edm_multi<D: Divergence + ?Sized>(
+    n: usize,
+    d: &mut D,
+    min_size: usize,
+    beta: f64,
+    degree: i32,
+    from: usize,
+) -> Vec<usize> {
+    let mut program = Program::new();
+    program.penalized(n, d, min_size, beta, degree, from);
+    program.breakouts()
+}
 
-            // check to see if optimal position of previous change point has changed
-            // if so update the left tree
-            if prev[t] > prev[t - 1] {
-                for i in prev[t - 1]..prev[t] {
-                    remove_element(&mut left_min, &mut left_max, z[i]);
-                }
-            } else if prev[t] < prev[t - 1] {
-                for i in prev[t]..prev[t - 1] {
-                    insert_element(&mut left_min, &mut left_max, z[i]);
-                }
-            }
+// Penalizes based on percent change in the statistic value.
+// Linear penalty means that each new breakout must result in an at least X% increase
+// Quadratic penalty means that each new breakout must result in at least an (X*k)% increase for k breakouts
+pub fn edm_percent<D: Divergence + ?Sized>(
+    n: usize,
+    d: &mut D,
+    min_size: usize,
+    percent: f64,
+    degree: i32,
+    from: usize,
+) -> Vec<usize> {
+    let mut program = Program::new();
+    program.percent(n, d, min_size, percent, degree, from);
+    program.breakouts()
+}
 
-            // calculate statistic value
-            let left_median = get_median(&left_min, &left_max);
-            let right_median = get_median(&right_min, &right_max);
-            let normalize = ((t - prev[t]) * (s - t)) as f64 / ((s - prev[t]) as f64).powf(2.0);
-            let tmp = f[t] + normalize * (left_median - right_median).powf(2.0)
-                - beta * g(number[t] as f64);
+// Arrays of the dynamic program for the first n observations
+// Values for a location only depend on earlier observations, so the arrays can be
+// extended when observations are appended
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    pub prev: Vec<usize>,
+    pub number: Vec<usize>,
+    pub f: Vec<f64>,
+}
 
-            // check for improved optimal statistic value
-            if tmp > f[s] {
-                number[s] = number[t] + 1;
-                f[s] = tmp;
-                prev[s] = t;
-            }
+impl Program {
+    pub fn new() -> Self {
+        Self {
+            prev: Vec::new(),
+            number: Vec::new(),
+            f: Vec::new(),
         }
     }
 
-    // obtain list of optimal change point estimates
-    let mut ret = Vec::new();
-    let mut at = n;
-    while at != 0 {
-        // don't insert 0 as a change point estimate
-        if prev[at] != 0 {
-            ret.push(prev[at]);
+    // identify which type of penalization to use
+    fn penalty(degree: i32) -> fn(f64) -> f64 {
+        match degree {
+            1 => linear,
+            2 => quadratic,
+            _ => constant,
         }
-        at = prev[at];
     }
-    ret.sort_unstable();
 
-    ret
-}
-
-// Penalizes based on percent change in the statistic value.
-// Linear penalty means that each new breakout must result in an at least X% increase
-// Quadratic penalty means that each new breakout must result in at least an (X*k)% increase for k breakouts
-pub fn edm_percent(z: &[f64], min_size: usize, percent: f64, degree: i32) -> Vec<usize> {
-    // identify which type of penalization to use
-    let g: fn(f64) -> f64 = match degree {
-        1 => linear,
-        2 => quadratic,
-        _ => constant,
-    };
-
-    let n = z.len();
-    let mut prev = vec![0; n + 1];
-    let mut number = vec![0; n + 1];
-    let mut f = vec![0.0; n + 1];
-
-    // trees used to store the "upper half" of the considered observations
-    let mut right_min = Multiset::new();
-    let mut left_min = Multiset::new();
-
-    // trees used to store the "lower half" of the considered observations
-    let mut right_max = Multiset::new();
-    let mut left_max = Multiset::new();
+    // Extends the arrays to n observations, returning the first new location
+    fn grow(&mut self, n: usize, initial: f64) -> usize {
+        let start = self.f.len();
+        self.prev.resize(n + 1, 0);
+        self.number.resize(n + 1, 0);
+        self.f.resize(n + 1, initial);
+        start
+    }
 
-    // iterate over possible locations for the last change
-    for s in 2 * min_size..n + 1 {
-        right_max.clear();
-        right_min.clear();
-        left_max.clear();
-        left_min.clear();
+    pub fn penalized<D: Divergence + ?Sized>(
+        &mut self,
+        n: usize,
+        d: &mut D,
+        min_size: usize,
+        beta: f64,
+        degree: i32,
+        from: usize,
+    ) {
+        // changes before from are not considered, so the prefix is a single segment
+        let locations: Vec<usize> = (min_size.max(from)..n + 1).collect();
+        self.penalized_at(n, d, min_size, beta, degree, &locations);
+    }
 
-        // initialize left and right trees to account for minimum segment size
-        for i in prev[min_size - 1]..min_size - 1 {
-            insert_element(&mut left_min, &mut left_max, z[i]);
+    // Like penalized, but changes are only considered at sorted locations, which must end with n
+    // and be at least min_size. Other locations keep their initial values, so time depends on the number of locations
+    pub fn penalized_at<D: Divergence + ?Sized>(
+        &mut self,
+        n: usize,
+        d: &mut D,
+        min_size: usize,
+        beta: f64,
+        degree: i32,
+        locations: &[usize],
+    ) {
+        let g = Self::penalty(degree);
+
+        let mut beta = beta;
+        // assume that beta is a positive number
+        if beta < 0.0 {
+            beta = -beta;
         }
-        for i in min_size - 1..s {
-            insert_element(&mut right_min, &mut right_max, z[i]);
+        let start = self.grow(n, -3.0);
+        let prev = &mut self.prev;
+        let number = &mut self.number;
+        let f = &mut self.f;
+
+        // iterate over possible locations for the last change
+        let first = locations.partition_point(|s| *s < start.max(2 * min_size));
+        for &s in &locations[first..] {
+            // iterate over possible locations for the penultimate change
+            // modify limits to deal with min_size
+            let end = locations.partition_point(|t| t + min_size <= s);
+            for &t in &locations[..end] {
+                // left segment is { Z[prev[t]], ..., Z[t-1] }
+                // right segment is { Z[t], ..., Z[s-1] }
+
+                // calculate statistic value
+                let normalize = ((t - prev[t]) * (s - t)) as f64 / ((s - prev[t]) as f64).powf(2.0);
+                let tmp = f[t] + normalize * d.between(prev[t], t, s) - beta * g(number[t] as f64);
+
+                // check for improved optimal statistic value
+                if tmp > f[s] {
+                    number[s] = number[t] + 1;
+                    f[s] = tmp;
+                    prev[s] = t;
+                }
+            }
         }
+    }
 
-        // iterate over possible locations for the penultiamte change
-        // modify limits to deal with min_size
-        for t in min_size..s - min_size + 1 {
-            // insert element into left tree
-            insert_element(&mut left_min, &mut left_max, z[t - 1]);
-            // remove element from right tree
-            remove_element(&mut right_min, &mut right_max, z[t - 1]);
-
-            // left tree now has { Z[prev[t-1]], ..., Z[t-1] }
-            // right tree now has { Z[t], ..., Z[s-1] }
+    pub fn percent<D: Divergence + ?Sized>(
+        &mut self,
+        n: usize,
+        d: &mut D,
+        min_size: usize,
+        percent: f64,
+        degree: i32,
+        from: usize,
+    ) {
+        // changes before from are not considered, so the prefix is a single segment
+        let locations: Vec<usize> = (min_size.max(from)..n + 1).collect();
+        self.percent_at(n, d, min_size, percent, degree, &locations);
+    }
 
-            // check to see if optimal position of previous change point has changed
-            // if so update the left tree
-            if prev[t] > prev[t - 1] {
-                for i in prev[t - 1]..prev[t] {
-                    remove_element(&mut left_min, &mut left_max, z[i]);
-                }
-            } else if prev[t] < prev[t - 1] {
-                for i in prev[t]..prev[t - 1] {
-                    insert_element(&mut left_min, &mut left_max, z[i]);
+    // Like percent, but changes are only considered at sorted locations, which must end with n
+    pub fn percent_at<D: Divergence + ?Sized>(
+        &mut self,
+        n: usize,
+        d: &mut D,
+        min_size: usize,
+        percent: f64,
+        degree: i32,
+        locations: &[usize],
+    ) {
+        let g = Self::penalty(degree);
+
+        let start = self.grow(n, 0.0);
+        let prev = &mut self.prev;
+        let number = &mut self.number;
+        let f = &mut self.f;
+
+        // iterate over possible locations for the last change
+        let first = locations.partition_point(|s| *s < start.max(2 * min_size));
+        for &s in &locations[first..] {
+            // iterate over possible locations for the penultiamte change
+            // modify limits to deal with min_size
+            let end = locations.partition_point(|t| t + min_size <= s);
+            for &t in &locations[..end] {
+                // left segment is { Z[prev[t]], ..., Z[t-1] }
+                // right segment is { Z[t], ..., Z[s-1] }
+
+                // calculate statistic value
+                let normalize = ((t - prev[t]) * (s - t)) as f64 / ((s - prev[t]) as f64).powf(2.0);
+                let tmp = f[t] + normalize * d.between(prev[t], t, s);
+
+                // find best location for change point. check % condition later
+                if tmp > f[s] {
+                    number[s] = number[t] + 1;
+                    f[s] = tmp;
+                    prev[s] = t;
                 }
             }
 
-            // calculate statistic value
-            let left_median = get_median(&left_min, &left_max);
-            let right_median = get_median(&right_min, &right_max);
-            let normalize = ((t - prev[t]) * (s - t)) as f64 / ((s - prev[t]) as f64).powf(2.0);
-            let tmp = f[t] + normalize * (left_median - right_median).powf(2.0);
-
-            // find best location for change point. check % condition later
-            if tmp > f[s] {
-                number[s] = number[t] + 1;
-                f[s] = tmp;
-                prev[s] = t;
+            // check to make sure we meet the percent change requirement
+            if prev[s] != 0 && f[s] - f[prev[s]] < percent * g(number[prev[s]] as f64) * f[prev[s]]
+            {
+                number[s] = number[prev[s]];
+                f[s] = f[prev[s]];
+                prev[s] = prev[prev[s]];
             }
         }
-
-        // check to make sure we meet the percent change requirement
-        if prev[s] != 0 && f[s] - f[prev[s]] < percent * g(number[prev[s]] as f64) * f[prev[s]] {
-            number[s] = number[prev[s]];
-            f[s] = f[prev[s]];
-            prev[s] = prev[prev[s]];
-        }
     }
 
-    // obtain list of optimal change point estimates
-    let mut ret = Vec::new();
-    let mut at = n;
-    while at != 0 {
-        // don't insert 0 as a change point estimate
-        if prev[at] != 0 {
-            ret.push(prev[at]);
+    // Returns the breakouts for all observations
+    pub fn breakouts(&self) -> Vec<usize> {
+        // obtain list of optimal change point estimates
+        let mut ret = Vec::new();
+        let mut at = self.f.len().saturating_sub(1);
+        while at != 0 {
+            // don't insert 0 as a change point estimate
+            if self.prev[at] != 0 {
+                ret.push(self.prev[at]);
+            }
+            at = self.prev[at];
         }
-        at = prev[at];
-    }
-    ret.sort_unstable();
+        ret.sort_unstable();
 
-    ret
+        ret
+    }
 }
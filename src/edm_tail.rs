@@ -71,7 +71,8 @@ fn get_quantile(x: &[f64], quant: f64) -> f64 {
     quant * (u - l) + l
 }
 
-pub fn edm_tail(z: &[f64], min_size: usize, alpha: f64) -> (usize, f64) {
+// Only considers breakouts at or after from
+pub fn edm_tail(z: &[f64], min_size: usize, alpha: f64, from: usize) -> (usize, f64) {
     let quant = 0.5;
     let n = z.len();
     let mut eps = (n as f64).ln().ceil() as i32;
@@ -79,12 +80,17 @@ pub fn edm_tail(z: &[f64], min_size: usize, alpha: f64) -> (usize, f64) {
 
     let mut info = Information::new(eps, min_size);
 
-    let mut tau1 = info.min_size;
-    let mut tau2 = tau1 * 2;
+    // start with the segments of min_size around the first location
+    let mut tau1 = info.min_size.max(from);
+    let offset = tau1 - info.min_size;
+    let mut tau2 = tau1 + info.min_size;
+    if tau2 > n {
+        return (0, info.best_stat);
+    }
 
     // Populate trees and calculate statistic value for starting configuration of
     // 2 min_size segments
-    for i in 0..tau1 {
+    for i in offset..tau1 {
         for j in i + 1..tau1 {
             let mut index = get_index(info.b, z[i] - z[j]);
             while index != 0 {
@@ -108,7 +114,7 @@ pub fn edm_tail(z: &[f64], min_size: usize, alpha: f64) -> (usize, f64) {
 
     // Populate trees and calculate statistic value for starting configuration of
     // 2 min_size segments
-    for i in 0..tau1 {
+    for i in offset..tau1 {
         for j in tau1..tau2 {
             let mut index = get_index(info.b, z[i] - z[j]);
             while index != 0 {
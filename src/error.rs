@@ -5,6 +5,7 @@ use std::fmt;
 #[derive(Debug, Eq, PartialEq)]
 pub enum Error {
     Parameter(String),
+    State(String),
 }
 
 impl error::Error for Error {}
@@ -13,6 +14,7 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::Parameter(ref err) => write!(f, "{}", err.as_str()),
+            Error::State(ref err) => write!(f, "{}", err.as_str()),
         }
     }
 }
@@ -0,0 +1,149 @@
+use crate::divergence::Features;
+use std::f64::consts::PI;
+
+/// A kernel for comparing distributions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Kernel {
+    /// exp(-d^2 / (2 h^2))
+    Gaussian,
+    /// exp(-|d| / h)
+    Laplace,
+}
+
+// number of frequencies used to approximate the kernel
+const FREQUENCIES: usize = 32;
+
+// maximum number of observations used for the median heuristic
+const HEURISTIC_SIZE: usize = 1000;
+
+impl Kernel {
+    // Returns evenly spaced quantiles of the spectral density of the kernel
+    // The kernel is the average of cos(w (x - y)) over these frequencies
+    pub(crate) fn frequencies(&self, bandwidth: f64) -> Vec<f64> {
+        (0..FREQUENCIES)
+            .map(|k| {
+                let p = (k as f64 + 0.5) / FREQUENCIES as f64;
+                let w = match self {
+                    Kernel::Gaussian => inverse_normal_cdf(p),
+                    Kernel::Laplace => (PI * (p - 0.5)).tan(),
+                };
+                w / bandwidth
+            })
+            .collect()
+    }
+}
+
+// Returns the spectral features of a scaled series
+// The bandwidth is in the units of the original series
+pub(crate) fn features(z: &[f64], kernel: Kernel, bandwidth: Option<f64>, denom: f64) -> Features {
+    let h = match bandwidth {
+        Some(b) => b / denom,
+        None => median_heuristic(z),
+    };
+    Features::new(z, &kernel.frequencies(h))
+}
+
+// Median of the pairwise distances between observations
+// Uses evenly spaced observations for long series
+pub(crate) fn median_heuristic(z: &[f64]) -> f64 {
+    let step = (z.len() + HEURISTIC_SIZE - 1) / HEURISTIC_SIZE;
+    let x: Vec<f64> = z.iter().step_by(step.max(1)).copied().collect();
+
+    let mut distances = Vec::with_capacity(x.len() * x.len() / 2);
+    for i in 0..x.len() {
+        for j in i + 1..x.len() {
+            let d = (x[i] - x[j]).abs();
+            // ignore ties so mostly constant series still have a bandwidth
+            if d > 0.0 {
+                distances.push(d);
+            }
+        }
+    }
+    if distances.is_empty() {
+        return 1.0;
+    }
+
+    let mid = distances.len() / 2;
+    *distances
+        .select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap())
+        .1
+}
+
+// Acklam's approximation, relative error of 1.15e-9
+fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+
+    let low = 0.02425;
+    if p < low {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - low {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -inverse_normal_cdf(1.0 - p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::{inverse_normal_cdf, median_heuristic};
+    use crate::Kernel;
+
+    #[test]
+    fn test_inverse_normal_cdf() {
+        assert!(inverse_normal_cdf(0.5).abs() < 1e-9);
+        assert!((inverse_normal_cdf(0.975) - 1.959963984540054).abs() < 1e-8);
+        assert!((inverse_normal_cdf(0.01) + 2.326347874040841).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_frequencies() {
+        // the average of cos(w d) approximates the kernel
+        for (kernel, expected) in [
+            (Kernel::Gaussian, (-0.5f64).exp()),
+            (Kernel::Laplace, (-1.0f64).exp()),
+        ] {
+            let w = kernel.frequencies(1.0);
+            let approx = w.iter().map(|w| w.cos()).sum::<f64>() / w.len() as f64;
+            assert!((approx - expected).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_median_heuristic() {
+        assert_eq!(median_heuristic(&[0.0, 1.0, 3.0]), 2.0);
+        assert_eq!(median_heuristic(&[1.0, 1.0]), 1.0);
+    }
+}
@@ -3,13 +3,51 @@
 #![allow(clippy::needless_range_loop)]
 
 mod amoc;
+mod anomaly;
+mod bootstrap;
+mod change;
+mod chunk;
+mod divergence;
 mod edm_multi;
 mod edm_tail;
 mod edmx;
 mod error;
+mod kernel;
+mod min_change;
 mod multi;
 mod multiset;
+mod pair;
+mod panel;
+mod prefilter;
+mod preprocess;
+mod prewhiten;
+mod quantile;
+mod rank_sum;
+mod ranks;
+mod recent;
+mod rolling;
+mod seasonality;
+mod sketch;
+mod state;
+mod statistic;
+mod target;
+mod trend;
+mod two_sample;
 
-pub use amoc::{amoc, AmocParams};
+pub use amoc::{amoc, AmocAnalysis, AmocParams, ScanWindow};
+pub use change::{Change, Direction};
 pub use error::Error;
-pub use multi::{multi, MultiParams};
+pub use kernel::Kernel;
+pub use min_change::MinChange;
+pub use multi::{multi, MultiAnalysis, MultiParams};
+pub use pair::{PairAnalysis, Relation};
+pub use panel::PanelAnalysis;
+pub use prefilter::Prefilter;
+pub use recent::Recent;
+pub use rolling::{rolling_median, RollingMedian, RollingQuantile};
+pub use sketch::QuantileSketch;
+pub use state::MultiState;
+pub use statistic::Statistic;
+pub use target::Target;
+pub use trend::Trend;
+pub use two_sample::{two_sample, TwoSample, TwoSampleParams};
@@ -0,0 +1,66 @@
+use crate::{Change, Error};
+
+/// The minimum change in median for a breakout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MinChange {
+    /// A change in the units of the series.
+    Absolute(f64),
+    /// A change relative to the median before the breakout.
+    Relative(f64),
+}
+
+impl MinChange {
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        let value = match *self {
+            MinChange::Absolute(v) => v,
+            MinChange::Relative(v) => v,
+        };
+        if value <= 0.0 {
+            return Err(Error::Parameter("min_change must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    // A change from a median of zero is always large enough in relative terms
+    pub(crate) fn satisfied(&self, change: &Change) -> bool {
+        match *self {
+            MinChange::Absolute(v) => change.absolute().abs() >= v,
+            MinChange::Relative(v) => change.relative().map_or(true, |r| r.abs() >= v),
+        }
+    }
+
+    // Returns the size of a change in the same terms as satisfied
+    pub(crate) fn size(&self, change: &Change) -> f64 {
+        match *self {
+            MinChange::Absolute(_) => change.absolute().abs(),
+            MinChange::Relative(_) => change.relative().map_or(f64::INFINITY, |r| r.abs()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::change::changes;
+    use crate::MinChange;
+
+    #[test]
+    fn test_satisfied() {
+        let series = [10.0, 10.0, 11.0, 11.0, 0.0, 0.0, 5.0, 5.0];
+        let changes = changes(&series, &[2, 4, 6]);
+        assert!(MinChange::Absolute(1.0).satisfied(&changes[0]));
+        assert!(!MinChange::Absolute(1.5).satisfied(&changes[0]));
+        assert!(!MinChange::Relative(0.2).satisfied(&changes[0]));
+        assert!(MinChange::Relative(0.2).satisfied(&changes[1]));
+        assert!(MinChange::Relative(0.2).satisfied(&changes[2]));
+    }
+
+    #[test]
+    fn test_size() {
+        let series = [10.0, 10.0, 11.0, 11.0, 0.0, 0.0, 5.0, 5.0];
+        let changes = changes(&series, &[2, 4, 6]);
+        assert_eq!(1.0, MinChange::Absolute(1.0).size(&changes[0]));
+        assert_eq!(0.1, MinChange::Relative(0.2).size(&changes[0]));
+        assert_eq!(1.0, MinChange::Relative(0.2).size(&changes[1]));
+        assert_eq!(f64::INFINITY, MinChange::Relative(0.2).size(&changes[2]));
+    }
+}
@@ -1,4 +1,23 @@
-use crate::Error;
+use crate::anomaly::anomalies;
+use crate::bootstrap::{interval, Rng};
+use crate::change::{changes, merge};
+use crate::chunk::chunked;
+use crate::divergence::{Counts, Divergence};
+use crate::edm_multi::{edm_multi, edm_percent, Program};
+use crate::edmx::edmx_from;
+use crate::kernel::median_heuristic;
+use crate::pair::{derive, PairAnalysis};
+use crate::panel::{Panel, PanelAnalysis};
+use crate::preprocess::{scale, Preprocess};
+use crate::rank_sum::rank_sum;
+use crate::statistic::with_divergence;
+use crate::{
+    Change, Direction, Error, Kernel, MinChange, MultiState, Prefilter, Recent, Relation,
+    Statistic, Target, Trend,
+};
+
+// maximum number of passes segmenting windows around breakouts found in chunks
+const PASSES: usize = 10;
 
 /// Parameters for detecting multiple breakouts.
 pub struct MultiParams {
@@ -6,6 +25,23 @@ pub struct MultiParams {
     degree: i32,
     beta: Option<f64>,
     percent: Option<f64>,
+    statistic: Statistic,
+    quantile: f64,
+    target: Target,
+    kernel: Kernel,
+    bandwidth: Option<f64>,
+    direction: Option<Direction>,
+    confidence: Option<f64>,
+    samples: usize,
+    seed: u64,
+    min_change: Option<MinChange>,
+    significance: Option<f64>,
+    refine: bool,
+    chunk_size: Option<usize>,
+    dispersion: Option<f64>,
+    anomaly_threshold: f64,
+    panel_fraction: f64,
+    preprocess: Preprocess,
 }
 
 /// Returns parameters for detecting multiple breakouts.
@@ -15,6 +51,62 @@ pub fn multi() -> MultiParams {
         degree: 1,
         beta: None,
         percent: None,
+        statistic: Statistic::Median,
+        quantile: 0.5,
+        target: Target::Location,
+        kernel: Kernel::Gaussian,
+        bandwidth: None,
+        direction: None,
+        confidence: None,
+        samples: 200,
+        seed: 0,
+        min_change: None,
+        significance: None,
+        refine: false,
+        chunk_size: None,
+        dispersion: None,
+        anomaly_threshold: 3.0,
+        panel_fraction: 1.0,
+        preprocess: Preprocess::new(),
+    }
+}
+
+/// The result of detecting multiple breakouts.
+#[derive(Debug)]
+pub struct MultiAnalysis {
+    breakouts: Vec<usize>,
+    changes: Vec<Change>,
+    intervals: Vec<Option<(usize, usize)>>,
+    filtered: Vec<usize>,
+    anomalies: Vec<usize>,
+}
+
+impl MultiAnalysis {
+    /// Returns the breakouts.
+    pub fn breakouts(&self) -> &[usize] {
+        &self.breakouts
+    }
+
+    /// Returns the change in median at each breakout.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Returns the confidence interval for the location of each breakout.
+    ///
+    /// Intervals are `None` when a neighboring segment has only `min_size` observations.
+    pub fn intervals(&self) -> &[Option<(usize, usize)>] {
+        &self.intervals
+    }
+
+    /// Returns the indices of observations modified by the prefilter.
+    pub fn filtered(&self) -> &[usize] {
+        &self.filtered
+    }
+
+    /// Returns the indices of point anomalies relative to their segment.
+    pub fn anomalies(&self) -> &[usize] {
+        &self.anomalies
     }
 }
 
@@ -49,8 +141,405 @@ impl MultiParams {
         self
     }
 
+    /// Sets the statistic used to compare segments.
+    pub fn statistic(&mut self, value: Statistic) -> &mut Self {
+        self.statistic = value;
+        self
+    }
+
+    /// Sets the quantile to compare between segments when using the median statistic.
+    pub fn quantile(&mut self, value: f64) -> &mut Self {
+        self.quantile = value;
+        self
+    }
+
+    /// Sets whether to detect changes in level or spread.
+    pub fn target(&mut self, value: Target) -> &mut Self {
+        self.target = value;
+        self
+    }
+
+    /// Sets the kernel when using the kernel statistic.
+    pub fn kernel(&mut self, value: Kernel) -> &mut Self {
+        self.kernel = value;
+        self
+    }
+
+    /// Sets the bandwidth of the kernel, in the units of the series.
+    ///
+    /// Defaults to the median distance between observations.
+    pub fn bandwidth<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<f64>>,
+    {
+        self.bandwidth = value.into();
+        self
+    }
+
+    /// Sets the dispersion for count data.
+    ///
+    /// Uses a negative binomial likelihood when set, and a Poisson likelihood otherwise.
+    pub fn dispersion<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<f64>>,
+    {
+        self.dispersion = value.into();
+        self
+    }
+
+    /// Sets the number of robust deviations from the segment median for an anomaly.
+    ///
+    /// Anomalies are reported by `analyze`. Seasonality and trends are removed first.
+    pub fn anomaly_threshold(&mut self, value: f64) -> &mut Self {
+        self.anomaly_threshold = value;
+        self
+    }
+
+    /// Sets the direction of breakouts to report.
+    ///
+    /// Segments around breakouts in the other direction are merged, so changes are
+    /// between reported segments.
+    pub fn direction<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<Direction>>,
+    {
+        self.direction = value.into();
+        self
+    }
+
+    /// Sets the minimum change in median for a breakout, in the units of the series.
+    pub fn min_change<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<MinChange>>,
+    {
+        self.min_change = value.into();
+        self
+    }
+
+    /// Sets the significance level for keeping a breakout.
+    ///
+    /// Adjacent segments that aren't significantly different with a rank-sum test are merged.
+    pub fn significance<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<f64>>,
+    {
+        self.significance = value.into();
+        self
+    }
+
+    /// Sets whether to refine each breakout with an exact single breakout search between its neighbors.
+    pub fn refine(&mut self, value: bool) -> &mut Self {
+        self.refine = value;
+        self
+    }
+
+    /// Sets the level of bootstrap confidence intervals for breakout locations.
+    pub fn confidence<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<f64>>,
+    {
+        self.confidence = value.into();
+        self
+    }
+
+    /// Sets the number of bootstrap samples for confidence intervals.
+    pub fn samples(&mut self, value: usize) -> &mut Self {
+        self.samples = value;
+        self
+    }
+
+    /// Sets the seed for bootstrap samples.
+    pub fn seed(&mut self, value: u64) -> &mut Self {
+        self.seed = value;
+        self
+    }
+
+    /// Sets the size of chunks for long series.
+    ///
+    /// Overlapping chunks are segmented independently, so memory and time are bounded
+    /// by the chunk size, and a window of at most two chunks around each breakout is
+    /// segmented again.
+    pub fn chunk_size<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<usize>>,
+    {
+        self.chunk_size = value.into();
+        self
+    }
+
+    /// Sets the fraction of series that must share a breakout when detecting breakouts in a panel.
+    pub fn panel_fraction(&mut self, value: f64) -> &mut Self {
+        self.panel_fraction = value;
+        self
+    }
+
+    /// Sets the filter for outliers applied before detection.
+    pub fn prefilter<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<Prefilter>>,
+    {
+        self.preprocess.prefilter = value.into();
+        self
+    }
+
+    /// Sets the period of the seasonality to remove before detection.
+    pub fn seasonality<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<usize>>,
+    {
+        self.preprocess.seasonality = value.into();
+        self
+    }
+
+    /// Sets the method for removing a linear trend before detection.
+    pub fn detrend<T>(&mut self, value: T) -> &mut Self
+    where
+        T: Into<Option<Trend>>,
+    {
+        self.preprocess.detrend = value.into();
+        self
+    }
+
+    /// Sets whether to remove autocorrelation before detection.
+    ///
+    /// Uses an autoregressive model with the order selected by AIC.
+    pub fn prewhiten(&mut self, value: bool) -> &mut Self {
+        self.preprocess.prewhiten = value;
+        self
+    }
+
+    /// Sets whether to replace observations with their ranks before detection.
+    ///
+    /// Ties get the average of their ranks. Breakouts don't change with monotone
+    /// transformations like `ln` or `sqrt`, but the bandwidth is in units of ranks.
+    pub fn rank(&mut self, value: bool) -> &mut Self {
+        self.preprocess.rank = value;
+        self
+    }
+
     /// Detects breakouts in a series.
     pub fn fit(&self, z: &[f64]) -> Result<Vec<usize>, Error> {
+        Ok(self.analyze(z)?.breakouts)
+    }
+
+    /// Detects breakouts in a series and returns details.
+    pub fn analyze(&self, z: &[f64]) -> Result<MultiAnalysis, Error> {
+        self.validate()?;
+        self.validate_analysis()?;
+        self.preprocess.validate()?;
+
+        let mut analysis = MultiAnalysis {
+            breakouts: Vec::new(),
+            changes: Vec::new(),
+            intervals: Vec::new(),
+            filtered: Vec::new(),
+            anomalies: Vec::new(),
+        };
+
+        if z.len() < self.min_size {
+            return Ok(analysis);
+        }
+
+        let prepared = self.preprocess.apply(z, self.min_size)?;
+        analysis.filtered = prepared.filtered;
+
+        // scale observations
+        let (zcounts, denom) = match scale(&prepared.z) {
+            Some(scaled) => scaled,
+            None => return Ok(analysis),
+        };
+
+        // changes are measured in the original series
+        let beta = self.beta.unwrap_or(0.008);
+        let breakouts = match self.chunk_size {
+            Some(size) if z.len() > size => self.segment_chunks(&zcounts, z, denom, beta, size),
+            _ => with_divergence!(self, &zcounts, denom, |d| self.segment(&mut d, z, beta, 0)),
+        };
+
+        analysis.anomalies = anomalies(&prepared.adjusted, &breakouts, self.anomaly_threshold);
+
+        let intervals = self.intervals(&zcounts, &breakouts, denom);
+
+        analysis.intervals = intervals;
+        analysis.breakouts = breakouts;
+        analysis.changes = changes(z, &analysis.breakouts);
+        Ok(analysis)
+    }
+
+    /// Detects breakouts in the relationship between two aligned series.
+    ///
+    /// Observations are dropped when either value is missing or the denominator is near zero.
+    pub fn analyze_pair(
+        &self,
+        a: &[f64],
+        b: &[f64],
+        relation: Relation,
+    ) -> Result<PairAnalysis, Error> {
+        let (z, kept) = derive(a, b, relation)?;
+        let breakouts = self.fit(&z)?;
+        Ok(PairAnalysis::new(a, b, &kept, &breakouts))
+    }
+
+    /// Detects breakouts in a series and returns a state that can be extended.
+    ///
+    /// Preprocessing and post-processing options are not supported.
+    pub fn fit_state(&self, z: &[f64]) -> Result<MultiState, Error> {
+        self.validate()?;
+        self.validate_analysis()?;
+        self.preprocess.unsupported("state")?;
+        self.unsupported("state")?;
+        if self.refine {
+            return Err(Error::Parameter(
+                "refine cannot be used with state".to_string(),
+            ));
+        }
+
+        // scale observations, keeping the scale for new observations
+        let (zcounts, min, denom) = match scale(z) {
+            Some((zcounts, denom)) => {
+                let min = z.iter().fold(f64::INFINITY, |a, b| a.min(*b));
+                (zcounts, min, denom)
+            }
+            None => (vec![0.0; z.len()], z.first().copied().unwrap_or(0.0), 1.0),
+        };
+        let bandwidth = match self.statistic {
+            Statistic::Kernel => Some(
+                self.bandwidth
+                    .unwrap_or_else(|| median_heuristic(&zcounts) * denom),
+            ),
+            _ => None,
+        };
+
+        let mut state = MultiState {
+            min_size: self.min_size,
+            degree: self.degree,
+            beta: self.beta.unwrap_or(0.008),
+            percent: self.percent,
+            statistic: self.statistic,
+            quantile: self.quantile,
+            target: self.target,
+            kernel: self.kernel,
+            bandwidth,
+            min,
+            denom,
+            z: zcounts,
+            program: Program::new(),
+        };
+        state.update();
+        Ok(state)
+    }
+
+    /// Detects breakouts shared by multiple aligned series.
+    ///
+    /// Each series is scaled separately, and a breakout must be shared by `panel_fraction`
+    /// of the series. A series participates in a breakout when its statistic between the
+    /// neighboring breakouts is at least half the statistic for the panel.
+    pub fn analyze_panel<T: AsRef<[f64]>>(&self, series: &[T]) -> Result<PanelAnalysis, Error> {
+        self.validate()?;
+        self.validate_analysis()?;
+        if self.panel_fraction <= 0.0 || self.panel_fraction > 1.0 {
+            return Err(Error::Parameter(
+                "panel_fraction must be between 0 and 1".to_string(),
+            ));
+        }
+        self.preprocess.validate()?;
+        self.unsupported("panel")?;
+
+        let n = series.first().map_or(0, |z| z.as_ref().len());
+        if series.iter().any(|z| z.as_ref().len() != n) {
+            return Err(Error::Parameter(
+                "series must have the same length".to_string(),
+            ));
+        }
+
+        let mut analysis = PanelAnalysis {
+            breakouts: Vec::new(),
+            participants: Vec::new(),
+        };
+
+        if n < self.min_size {
+            return Ok(analysis);
+        }
+
+        // scale each series, so constant series don't contribute
+        let mut scaled = Vec::with_capacity(series.len());
+        for z in series {
+            let prepared = self.preprocess.apply(z.as_ref(), self.min_size)?;
+            scaled.push(scale(&prepared.z).unwrap_or_else(|| (vec![0.0; n], 1.0)));
+        }
+
+        let divergences = scaled
+            .iter()
+            .map(|(zcounts, denom)| self.divergence(zcounts, *denom))
+            .collect();
+        let count = (self.panel_fraction * series.len() as f64).ceil().max(1.0) as usize;
+        let mut d = Panel::new(divergences, count);
+
+        let beta = self.beta.unwrap_or(0.008);
+        let breakouts = self.detect(&mut d, n, beta, 0);
+        let mut breakouts = if self.refine {
+            refine(&mut d, &breakouts, n, self.min_size, 0)
+        } else {
+            breakouts
+        };
+
+        // compare the statistic of each series between the neighbors to the panel
+        // breakouts with fewer participants than the count are merged, weakest first
+        loop {
+            let mut weakest: Option<(usize, f64)> = None;
+            analysis.participants.clear();
+            for (i, t) in breakouts.iter().enumerate() {
+                let start = if i == 0 { 0 } else { breakouts[i - 1] };
+                let end = breakouts.get(i + 1).copied().unwrap_or(n);
+                let shared = d.between(start, *t, end);
+                let participants: Vec<usize> = d
+                    .each(start, *t, end)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, v)| *v > 0.0 && *v >= 0.5 * shared)
+                    .map(|(j, _)| j)
+                    .collect();
+                if participants.len() < count && weakest.map_or(true, |(_, s)| shared < s) {
+                    weakest = Some((i, shared));
+                }
+                analysis.participants.push(participants);
+            }
+            match weakest {
+                Some((i, _)) => {
+                    breakouts.remove(i);
+                }
+                None => break,
+            }
+        }
+        analysis.breakouts = breakouts;
+        Ok(analysis)
+    }
+
+    /// Detects breakouts in a series of counts.
+    ///
+    /// Uses a likelihood for count data instead of the statistic, quantile, and target.
+    /// The penalization term is in units of log likelihood and defaults to `ln(n)`.
+    pub fn fit_counts(&self, z: &[u64]) -> Result<Vec<usize>, Error> {
+        self.validate()?;
+        if let Some(dispersion) = self.dispersion {
+            if !(dispersion.is_finite() && dispersion > 0.0) {
+                return Err(Error::Parameter("dispersion must be positive".to_string()));
+            }
+        }
+        self.preprocess.unsupported("counts")?;
+
+        if z.len() < self.min_size || z.iter().all(|v| *v == z[0]) {
+            return Ok(Vec::new());
+        }
+
+        // beta is in units of log likelihood, so use a BIC-like default
+        let beta = self.beta.unwrap_or((z.len() as f64).ln());
+        let mut d = Counts::new(z, self.dispersion);
+        let z: Vec<f64> = z.iter().map(|v| *v as f64).collect();
+        Ok(self.segment(&mut d, &z, beta, 0))
+    }
+
+    fn validate(&self) -> Result<(), Error> {
         if self.min_size < 2 {
             return Err(Error::Parameter("min_size must be at least 2".to_string()));
         }
@@ -62,41 +551,397 @@ impl MultiParams {
         if self.degree < 0 || self.degree > 2 {
             return Err(Error::Parameter("degree must be 0, 1, or 2".to_string()));
         }
+        if let Some(min_change) = &self.min_change {
+            min_change.validate()?;
+        }
+        if let Some(chunk_size) = self.chunk_size {
+            if chunk_size < 8 * self.min_size {
+                return Err(Error::Parameter(
+                    "chunk_size must be at least 8 times min_size".to_string(),
+                ));
+            }
+        }
+        if let Some(significance) = self.significance {
+            if significance <= 0.0 || significance >= 1.0 {
+                return Err(Error::Parameter(
+                    "significance must be between 0 and 1".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Detects the most recent breakout in the last `k` observations.
+    ///
+    /// Only breakouts in the last `k` observations are considered, which is faster than `fit`.
+    /// Earlier observations are treated as a single segment.
+    pub fn recent(&self, z: &[f64], k: usize) -> Result<Option<Recent>, Error> {
+        self.validate()?;
+        self.validate_analysis()?;
+        if k < self.min_size {
+            return Err(Error::Parameter("k must be at least min_size".to_string()));
+        }
+        self.preprocess.validate()?;
 
         if z.len() < self.min_size {
-            return Ok(Vec::new());
+            return Ok(None);
         }
 
-        // scale observations
-        let min = z.iter().min_by(|i, j| i.partial_cmp(j).unwrap()).unwrap();
-        let max = z.iter().max_by(|i, j| i.partial_cmp(j).unwrap()).unwrap();
-        let denom = max - min;
-        if denom == 0.0 {
-            return Ok(Vec::new());
+        let prepared = self.preprocess.apply(z, self.min_size)?;
+        let (zcounts, denom) = match scale(&prepared.z) {
+            Some(scaled) => scaled,
+            None => return Ok(None),
+        };
+
+        let n = zcounts.len();
+        let from = n.saturating_sub(k);
+        let beta = self.beta.unwrap_or(0.008);
+        let (breakouts, statistic) = with_divergence!(self, &zcounts, denom, |d| {
+            let breakouts = self.segment(&mut d, z, beta, from);
+
+            // same statistic as a single breakout between the neighbors
+            let statistic = match breakouts.last() {
+                Some(&t) => {
+                    let start = breakouts.iter().rev().nth(1).copied().unwrap_or(0);
+                    d.between(start, t, n) * ((t - start) * (n - t)) as f64 / (n - start) as f64
+                }
+                None => 0.0,
+            };
+            (breakouts, statistic)
+        });
+
+        let change = match changes(z, &breakouts).pop() {
+            Some(change) => change,
+            None => return Ok(None),
+        };
+        if self.direction.is_some() && change.direction() != self.direction {
+            return Ok(None);
+        }
+        Ok(Some(Recent::new(change.index(), statistic)))
+    }
+
+    // Returns an error naming the first post-processing option set, for methods without it
+    fn unsupported(&self, method: &str) -> Result<(), Error> {
+        let option = if self.min_change.is_some() {
+            "min_change"
+        } else if self.significance.is_some() {
+            "significance"
+        } else if self.direction.is_some() {
+            "direction"
+        } else if self.confidence.is_some() {
+            "confidence"
+        } else if self.chunk_size.is_some() {
+            "chunk_size"
+        } else {
+            return Ok(());
+        };
+        Err(Error::Parameter(format!(
+            "{} cannot be used with {}",
+            option, method
+        )))
+    }
+
+    fn validate_analysis(&self) -> Result<(), Error> {
+        if !(0.0..=1.0).contains(&self.quantile) {
+            return Err(Error::Parameter(
+                "quantile must be between 0 and 1".to_string(),
+            ));
+        }
+        if self.quantile != 0.5 {
+            if self.statistic != Statistic::Median {
+                return Err(Error::Parameter(
+                    "quantile requires median statistic".to_string(),
+                ));
+            }
+            if self.target == Target::Scale {
+                return Err(Error::Parameter(
+                    "quantile requires location target".to_string(),
+                ));
+            }
+        }
+        if self.target == Target::Scale && self.statistic == Statistic::Kernel {
+            return Err(Error::Parameter(
+                "scale target requires median or mean statistic".to_string(),
+            ));
+        }
+        if let Some(bandwidth) = self.bandwidth {
+            if !(bandwidth.is_finite() && bandwidth > 0.0) {
+                return Err(Error::Parameter("bandwidth must be positive".to_string()));
+            }
+        }
+        if !(self.anomaly_threshold.is_finite() && self.anomaly_threshold > 0.0) {
+            return Err(Error::Parameter(
+                "anomaly_threshold must be positive".to_string(),
+            ));
+        }
+        if let Some(confidence) = self.confidence {
+            if confidence <= 0.0 || confidence >= 1.0 {
+                return Err(Error::Parameter(
+                    "confidence must be between 0 and 1".to_string(),
+                ));
+            }
+            if self.samples == 0 {
+                return Err(Error::Parameter("samples must be positive".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    // Returns a bootstrap confidence interval for each breakout, resampling its neighbors
+    fn intervals(&self, z: &[f64], breakouts: &[usize], denom: f64) -> Vec<Option<(usize, usize)>> {
+        let level = match self.confidence {
+            Some(level) => level,
+            None => return Vec::new(),
+        };
+        let mut rng = Rng::new(self.seed);
+        (0..breakouts.len())
+            .map(|i| {
+                let start = if i == 0 { 0 } else { breakouts[i - 1] };
+                let end = breakouts.get(i + 1).copied().unwrap_or(z.len());
+                interval(
+                    z,
+                    (start, breakouts[i], end),
+                    self.min_size,
+                    self.samples,
+                    level,
+                    &mut rng,
+                    |x| self.divergence(x, denom),
+                )
+            })
+            .collect()
+    }
+
+    // Returns the divergence for the statistic and target of a scaled series
+    // Boxed for bootstrap samples and panels, which don't need static dispatch
+    fn divergence<'a>(&self, z: &'a [f64], denom: f64) -> Box<dyn Divergence + 'a> {
+        with_divergence!(self, z, denom, |d| Box::new(d) as Box<dyn Divergence + 'a>)
+    }
+
+    // Detects breakouts, then merges and refines segments
+    // The series is in the original units, for comparing segments
+    // Only breakouts at or after from are considered
+    fn segment<D: Divergence + ?Sized>(
+        &self,
+        d: &mut D,
+        z: &[f64],
+        beta: f64,
+        from: usize,
+    ) -> Vec<usize> {
+        let n = z.len();
+        let breakouts = self.detect(d, n, beta, from);
+        let breakouts = self.merge(z, breakouts);
+        if self.refine {
+            refine(d, &breakouts, n, self.min_size, from)
+        } else {
+            breakouts
+        }
+    }
+
+    // Segments overlapping chunks of the scaled series, then segments a window around each
+    // breakout again, since chunks only see part of the neighboring segments
+    // Windows span at most two chunks, each with its own divergence, so memory is bounded
+    // by the chunk size
+    fn segment_chunks(
+        &self,
+        zcounts: &[f64],
+        z: &[f64],
+        denom: f64,
+        beta: f64,
+        size: usize,
+    ) -> Vec<usize> {
+        let n = z.len();
+        let mut breakouts = chunked(n, size, |start, end| {
+            let breakouts = with_divergence!(self, &zcounts[start..end], denom, |d| {
+                self.detect(&mut d, end - start, beta, 0)
+            });
+            breakouts.into_iter().map(|b| start + b).collect()
+        });
+
+        // segment again with the two neighbors on each side, to fix the number of breakouts
+        let mut i = 0;
+        while i < breakouts.len() {
+            let b = breakouts[i];
+            let (start, end) = window(
+                if i >= 2 { breakouts[i - 2] } else { 0 },
+                b,
+                breakouts.get(i + 2).copied().unwrap_or(n),
+                size,
+            );
+            let first = breakouts.partition_point(|x| *x <= start);
+            let last = breakouts.partition_point(|x| *x < end);
+            let found = self.resegment(zcounts, denom, beta, start, None, end);
+            breakouts.splice(first..last, found);
+            i = breakouts.partition_point(|x| *x <= b);
         }
-        let zcounts: Vec<f64> = z.iter().map(|x| (x - min) / denom).collect();
 
-        if self.percent.is_some() {
-            Ok(crate::edm_multi::edm_percent(
-                &zcounts,
-                self.min_size,
-                self.percent.unwrap(),
-                self.degree,
-            ))
+        // segment again between the neighbors, keeping the left one, to fix locations
+        // windows capped at two chunks can disagree, so stop after a few passes
+        for _ in 0..PASSES {
+            let mut changed = false;
+            let mut i = 0;
+            while i < breakouts.len() {
+                let b = breakouts[i];
+                let before = if i >= 2 { breakouts[i - 2] } else { 0 };
+                let left = if i >= 1 { breakouts[i - 1] } else { 0 };
+                let right = breakouts.get(i + 1).copied().unwrap_or(n);
+                let (start, end) = window(before, b, right, size);
+
+                // keep the left neighbor when its previous breakout is in the window
+                let (start, fixed) = if start == before && left > before {
+                    (start, Some(left))
+                } else {
+                    (start.max(left), None)
+                };
+                let found = self.resegment(zcounts, denom, beta, start, fixed, end);
+                let first = breakouts.partition_point(|x| *x <= fixed.unwrap_or(start));
+                let last = breakouts.partition_point(|x| *x < end);
+                if breakouts[first..last] != found[..] {
+                    breakouts.splice(first..last, found);
+                    changed = true;
+                }
+                i = breakouts.partition_point(|x| *x <= b);
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let breakouts = self.merge(z, breakouts);
+        if !self.refine {
+            return breakouts;
+        }
+
+        let mut refined: Vec<usize> = Vec::with_capacity(breakouts.len());
+        for i in 0..breakouts.len() {
+            let b = breakouts[i];
+            let (start, end) = window(
+                refined.last().copied().unwrap_or(0),
+                b,
+                breakouts.get(i + 1).copied().unwrap_or(n),
+                size,
+            );
+            let loc = with_divergence!(self, &zcounts[start..end], denom, |d| {
+                refine(&mut d, &[b - start], end - start, self.min_size, 0)[0]
+            });
+            refined.push(start + loc);
+        }
+        refined
+    }
+
+    // Segments the scaled series between start and end, optionally keeping a breakout whose
+    // previous breakout is start
+    // This makes the same choices as the program on the whole series when start, the fixed
+    // breakout, and end are on its path
+    fn resegment(
+        &self,
+        zcounts: &[f64],
+        denom: f64,
+        beta: f64,
+        start: usize,
+        fixed: Option<usize>,
+        end: usize,
+    ) -> Vec<usize> {
+        let n = end - start;
+        let mut locations = Vec::new();
+        let first = match fixed {
+            Some(f) => {
+                locations.push(f - start);
+                f - start + self.min_size
+            }
+            None => self.min_size,
+        };
+        let last = (n + 1).saturating_sub(self.min_size).max(first);
+        locations.extend(first..last);
+        locations.push(n);
+
+        let mut program = Program::new();
+        with_divergence!(self, &zcounts[start..end], denom, |d| match self.percent {
+            Some(percent) => {
+                program.percent_at(n, &mut d, self.min_size, percent, self.degree, &locations)
+            }
+            None => program.penalized_at(n, &mut d, self.min_size, beta, self.degree, &locations),
+        });
+        program
+            .breakouts()
+            .into_iter()
+            .map(|b| start + b)
+            .filter(|b| Some(*b) != fixed)
+            .collect()
+    }
+
+    fn detect<D: Divergence + ?Sized>(
+        &self,
+        d: &mut D,
+        n: usize,
+        beta: f64,
+        from: usize,
+    ) -> Vec<usize> {
+        if let Some(percent) = self.percent {
+            edm_percent(n, d, self.min_size, percent, self.degree, from)
         } else {
-            Ok(crate::edm_multi::edm_multi(
-                &zcounts,
-                self.min_size,
-                self.beta.unwrap_or(0.008),
-                self.degree,
-            ))
+            edm_multi(n, d, self.min_size, beta, self.degree, from)
+        }
+    }
+
+    // Merges segments with small or insignificant changes, or changes in the other direction
+    fn merge(&self, z: &[f64], breakouts: Vec<usize>) -> Vec<usize> {
+        if self.min_change.is_none() && self.significance.is_none() && self.direction.is_none() {
+            return breakouts;
         }
+        // rank failing changes by the same quantity the test uses
+        let strength = |x: &[f64], y: &[f64], c: &Change| match (self.min_change, self.significance)
+        {
+            (Some(m), _) => m.size(c),
+            (None, Some(_)) => -rank_sum(x, y),
+            (None, None) => c.absolute().abs(),
+        };
+        merge(
+            z,
+            &breakouts,
+            |x, y, c| {
+                self.min_change.map_or(true, |m| m.satisfied(c))
+                    && self.significance.map_or(true, |a| rank_sum(x, y) <= a)
+                    && (self.direction.is_none() || c.direction() == self.direction)
+            },
+            strength,
+        )
+    }
+}
+
+// Returns the range between start and end around b, spanning at most two chunks
+fn window(start: usize, b: usize, end: usize, size: usize) -> (usize, usize) {
+    let lo = start.max(b.saturating_sub(size));
+    let hi = end.min(b + size);
+    // use the part one side doesn't need on the other
+    (
+        start.max(hi.saturating_sub(2 * size)),
+        end.min(lo + 2 * size),
+    )
+}
+
+// Moves each breakout to the best single breakout between its neighbors
+fn refine<D: Divergence + ?Sized>(
+    d: &mut D,
+    breakouts: &[usize],
+    n: usize,
+    min_size: usize,
+    from: usize,
+) -> Vec<usize> {
+    let mut refined = Vec::with_capacity(breakouts.len());
+    for i in 0..breakouts.len() {
+        let start = refined.last().copied().unwrap_or(0);
+        let end = breakouts.get(i + 1).copied().unwrap_or(n);
+        refined.push(edmx_from(start, end, from, d, min_size).0);
     }
+    refined
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Error;
+    use crate::divergence::Trackers;
+    use crate::multi::refine;
+    use crate::quantile::Quantile;
+    use crate::{Direction, Error, Kernel, MinChange, Prefilter, Statistic, Target, Trend};
 
     #[rustfmt::skip]
     fn generate_series() -> Vec<f64> {
@@ -107,6 +952,16 @@ mod tests {
         ]
     }
 
+    // segments of 10 observations around 5, 8, and 3
+    #[rustfmt::skip]
+    fn generate_levels() -> Vec<f64> {
+        vec![
+            5.0, 5.0, 6.0, 5.0, 4.0, 5.0, 5.0, 6.0, 5.0, 4.0,
+            8.0, 9.0, 8.0, 7.0, 8.0, 8.0, 9.0, 8.0, 7.0, 8.0,
+            3.0, 2.0, 3.0, 4.0, 3.0, 3.0, 2.0, 3.0, 4.0, 3.0
+        ]
+    }
+
     #[test]
     fn test_multi() {
         let series = generate_series();
@@ -126,40 +981,465 @@ mod tests {
     }
 
     #[test]
-    fn test_empty() {
-        let series = Vec::new();
-        let breakouts = crate::multi().fit(&series).unwrap();
-        assert!(breakouts.is_empty());
-    }
-
-    #[test]
-    fn test_constant() {
-        let series = vec![1.0; 100];
-        let breakouts = crate::multi().fit(&series).unwrap();
-        assert!(breakouts.is_empty());
+    fn test_mean() {
+        let series = generate_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .statistic(Statistic::Mean)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10], breakouts);
     }
 
     #[test]
-    fn test_almost_constant() {
-        let mut series = vec![1.0; 100];
-        series[50] = 2.0;
-        let breakouts = crate::multi().fit(&series).unwrap();
-        assert!(breakouts.is_empty());
+    fn test_quantile() {
+        let series = generate_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .quantile(0.9)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10], breakouts);
     }
 
-    #[test]
     #[rustfmt::skip]
-    fn test_simple() {
-        let series = vec![
-            0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
-            1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0
-        ];
-        let breakouts = crate::multi().min_size(5).fit(&series).unwrap();
-        assert_eq!(vec![10], breakouts);
+    fn generate_scale_series() -> Vec<f64> {
+        vec![
+            5.0, 5.1, 4.9, 5.0, 5.1, 4.9, 5.0, 5.1, 4.9, 5.0,
+            8.0, 2.0, 7.0, 3.0, 9.0, 1.0, 6.0, 4.0, 0.0, 10.0
+        ]
     }
 
     #[test]
-    fn test_bad_min_size() {
+    fn test_scale() {
+        let series = generate_scale_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .target(Target::Scale)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10], breakouts);
+    }
+
+    #[test]
+    fn test_scale_mean() {
+        let series = generate_scale_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .target(Target::Scale)
+            .statistic(Statistic::Mean)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10], breakouts);
+    }
+
+    #[test]
+    fn test_kernel() {
+        let series = generate_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .statistic(Statistic::Kernel)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10, 20], breakouts);
+    }
+
+    #[test]
+    fn test_kernel_distribution() {
+        let series = generate_scale_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .statistic(Statistic::Kernel)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10], breakouts);
+    }
+
+    #[test]
+    fn test_kernel_laplace() {
+        let series = generate_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .statistic(Statistic::Kernel)
+            .kernel(Kernel::Laplace)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10, 20], breakouts);
+    }
+
+    #[rustfmt::skip]
+    fn generate_counts() -> Vec<u64> {
+        vec![
+            0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0,
+            2, 1, 3, 1, 2, 0, 2, 1, 1, 3, 2, 1, 2, 2, 1, 0, 3, 1, 2, 1
+        ]
+    }
+
+    #[test]
+    fn test_counts() {
+        let series = generate_counts();
+        let breakouts = crate::multi().min_size(5).fit_counts(&series).unwrap();
+        assert_eq!(vec![20], breakouts);
+    }
+
+    #[test]
+    fn test_counts_dispersion() {
+        let series = generate_counts();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .dispersion(1.0)
+            .fit_counts(&series)
+            .unwrap();
+        assert_eq!(vec![20], breakouts);
+    }
+
+    #[test]
+    fn test_counts_constant() {
+        let series = vec![0; 100];
+        let breakouts = crate::multi().fit_counts(&series).unwrap();
+        assert!(breakouts.is_empty());
+    }
+
+    fn generate_seasonal_series() -> Vec<f64> {
+        let pattern = [0.0, 2.0, 4.0, 6.0, 4.0, 2.0, 0.0];
+        (0..70)
+            .map(|i| pattern[i % 7] + if i < 35 { 0.0 } else { 3.0 } + (i % 3) as f64 * 0.1)
+            .collect()
+    }
+
+    #[test]
+    fn test_seasonality() {
+        let series = generate_seasonal_series();
+        let breakouts = crate::multi()
+            .min_size(10)
+            .seasonality(7)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![35], breakouts);
+    }
+
+    #[test]
+    fn test_seasonality_changes() {
+        let series = generate_seasonal_series();
+        let analysis = crate::multi()
+            .min_size(10)
+            .seasonality(7)
+            .analyze(&series)
+            .unwrap();
+        let change = &analysis.changes()[0];
+        assert_eq!(2.2, change.before());
+        assert_eq!(5.2, change.after());
+    }
+
+    fn generate_trend_series() -> Vec<f64> {
+        (0..100)
+            .map(|i| 0.1 * i as f64 + [0.0, 1.0, 0.5, 2.0, 1.5][i % 5])
+            .collect()
+    }
+
+    #[test]
+    fn test_trend() {
+        let series = generate_trend_series();
+        let breakouts = crate::multi().min_size(10).fit(&series).unwrap();
+        assert_eq!(vec![40], breakouts);
+    }
+
+    #[test]
+    fn test_detrend() {
+        let series = generate_trend_series();
+        let breakouts = crate::multi()
+            .min_size(10)
+            .detrend(Trend::TheilSen)
+            .fit(&series)
+            .unwrap();
+        assert!(breakouts.is_empty());
+    }
+
+    // returns innovations and an autoregressive series built from them
+    fn generate_autocorrelated_series() -> (Vec<f64>, Vec<f64>) {
+        let mut seed: u64 = 42;
+        let innovations: Vec<f64> = (0..300)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect();
+        let mut x = 0.0;
+        let series = innovations
+            .iter()
+            .map(|e| {
+                x = 0.9 * x + e;
+                x
+            })
+            .collect();
+        (innovations, series)
+    }
+
+    #[test]
+    fn test_prewhiten() {
+        let (innovations, series) = generate_autocorrelated_series();
+        let expected = crate::multi().fit(&innovations).unwrap();
+        assert_ne!(expected, crate::multi().fit(&series).unwrap());
+        let breakouts = crate::multi().prewhiten(true).fit(&series).unwrap();
+        assert_eq!(expected, breakouts);
+    }
+
+    #[test]
+    fn test_rank() {
+        let series = generate_series();
+        let transformed: Vec<f64> = series.iter().map(|v| v.exp()).collect();
+        for (statistic, expected) in [
+            (Statistic::Median, vec![10, 15, 20]),
+            (Statistic::Mean, vec![10]),
+        ] {
+            let mut params = crate::multi();
+            params.min_size(5).statistic(statistic).rank(true);
+            assert_eq!(expected, params.fit(&series).unwrap());
+            assert_eq!(expected, params.fit(&transformed).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_prefilter() {
+        let mut series = generate_series();
+        series[2] = 100.0;
+        let breakouts = crate::multi().min_size(5).fit(&series).unwrap();
+        assert!(breakouts.is_empty());
+
+        let analysis = crate::multi()
+            .min_size(5)
+            .prefilter(Prefilter::Hampel {
+                window: 7,
+                threshold: 5.0,
+            })
+            .analyze(&series)
+            .unwrap();
+        assert_eq!(vec![10, 15, 20], analysis.breakouts());
+        assert_eq!(vec![2], analysis.filtered());
+    }
+
+    #[test]
+    fn test_changes() {
+        let series = generate_series();
+        let analysis = crate::multi().min_size(5).analyze(&series).unwrap();
+        let changes = analysis.changes();
+        assert_eq!(vec![10, 15, 20], analysis.breakouts());
+        assert_eq!(
+            vec![3.0, -1.0, 5.0],
+            changes.iter().map(|c| c.absolute()).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(1.5), changes[0].relative());
+        assert_eq!(Some(Direction::Decrease), changes[1].direction());
+    }
+
+    #[test]
+    fn test_direction() {
+        let series = generate_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .direction(Direction::Increase)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10, 20], breakouts);
+
+        let breakouts = crate::multi()
+            .min_size(5)
+            .direction(Direction::Decrease)
+            .fit(&series)
+            .unwrap();
+        assert!(breakouts.is_empty());
+    }
+
+    #[test]
+    fn test_direction_merge() {
+        let series = generate_levels();
+        let analysis = crate::multi().min_size(5).analyze(&series).unwrap();
+        assert_eq!(&[10, 20], analysis.breakouts());
+
+        // the change is from the merged segment before the hidden breakout
+        let analysis = crate::multi()
+            .min_size(5)
+            .direction(Direction::Decrease)
+            .analyze(&series)
+            .unwrap();
+        assert_eq!(&[20], analysis.breakouts());
+        let change = &analysis.changes()[0];
+        assert_eq!(6.5, change.before());
+        assert_eq!(3.0, change.after());
+    }
+
+    #[test]
+    fn test_min_change() {
+        let series = generate_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .min_change(MinChange::Absolute(2.0))
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10, 20], breakouts);
+
+        let breakouts = crate::multi()
+            .min_size(5)
+            .min_change(MinChange::Relative(1.3))
+            .fit(&series)
+            .unwrap();
+        // 4 to 9 is a larger absolute change than 2 to 4.5, but a smaller relative one
+        assert_eq!(vec![10], breakouts);
+    }
+
+    #[test]
+    fn test_significance() {
+        let series = generate_series();
+        let breakouts = crate::multi()
+            .min_size(5)
+            .significance(0.05)
+            .fit(&series)
+            .unwrap();
+        assert_eq!(vec![10, 20], breakouts);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_refine() {
+        let series = vec![
+            0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0
+        ];
+        let mut d = Trackers::new(&series, Quantile::new(0.5), Quantile::new(0.5));
+        assert_eq!(vec![10], refine(&mut d, &[7], series.len(), 5, 0));
+    }
+
+    #[test]
+    fn test_confidence() {
+        let series = generate_series();
+        let analysis = crate::multi()
+            .min_size(5)
+            .confidence(0.9)
+            .analyze(&series)
+            .unwrap();
+        // neighboring segments only have min_size observations
+        assert_eq!(&[None, None, None], analysis.intervals());
+
+        let series = generate_levels();
+        let analysis = crate::multi()
+            .min_size(5)
+            .confidence(0.9)
+            .analyze(&series)
+            .unwrap();
+        assert_eq!(&[Some((10, 12)), Some((20, 22))], analysis.intervals());
+    }
+
+    #[test]
+    fn test_recent() {
+        let series = generate_series();
+        let recent = crate::multi()
+            .min_size(5)
+            .recent(&series, 12)
+            .unwrap()
+            .unwrap();
+        assert_eq!(19, recent.index());
+        assert!(recent.statistic() > 0.0);
+
+        let recent = crate::multi()
+            .min_size(5)
+            .direction(Direction::Decrease)
+            .recent(&series, 12)
+            .unwrap();
+        assert_eq!(None, recent);
+    }
+
+    #[test]
+    fn test_chunk_size() {
+        let mut seed: u64 = 1;
+        let series: Vec<f64> = (0..400)
+            .map(|i| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let noise = (seed >> 11) as f64 / (1u64 << 53) as f64;
+                0.1 * noise + [0.0, 3.0, 1.0, 4.0][i / 100]
+            })
+            .collect();
+        let breakouts = crate::multi().min_size(20).fit(&series).unwrap();
+        assert_eq!(vec![100, 200, 300], breakouts);
+
+        for size in [160, 200, 250, 300, 399] {
+            let chunked = crate::multi()
+                .min_size(20)
+                .chunk_size(size)
+                .fit(&series)
+                .unwrap();
+            assert_eq!(breakouts, chunked);
+        }
+    }
+
+    #[test]
+    fn test_chunk_cuts() {
+        let mut seed: u64 = 5;
+        let series: Vec<f64> = (0..600)
+            .map(|i| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let noise = (seed >> 11) as f64 / (1u64 << 53) as f64;
+                0.1 * noise + [0.0, 3.0, 1.0, 4.0, 2.0, 5.0][i / 100]
+            })
+            .collect();
+        let breakouts = crate::multi().min_size(20).fit(&series).unwrap();
+        assert_eq!(vec![101, 200, 300, 400, 500], breakouts);
+
+        // sizes with a cut between chunks within one observation of a breakout
+        for size in [184, 228, 342, 457] {
+            let chunked = crate::multi()
+                .min_size(20)
+                .chunk_size(size)
+                .fit(&series)
+                .unwrap();
+            assert_eq!(breakouts, chunked);
+        }
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let mut series = generate_series();
+        series[3] = 9.0;
+        series[26] = 4.0;
+        let analysis = crate::multi().min_size(5).analyze(&series).unwrap();
+        assert_eq!(vec![10, 15, 20], analysis.breakouts());
+        assert_eq!(vec![3, 26], analysis.anomalies());
+    }
+
+    #[test]
+    fn test_empty() {
+        let series = Vec::new();
+        let breakouts = crate::multi().fit(&series).unwrap();
+        assert!(breakouts.is_empty());
+    }
+
+    #[test]
+    fn test_constant() {
+        let series = vec![1.0; 100];
+        let breakouts = crate::multi().fit(&series).unwrap();
+        assert!(breakouts.is_empty());
+    }
+
+    #[test]
+    fn test_almost_constant() {
+        let mut series = vec![1.0; 100];
+        series[50] = 2.0;
+        let breakouts = crate::multi().fit(&series).unwrap();
+        assert!(breakouts.is_empty());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_simple() {
+        let series = vec![
+            0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0
+        ];
+        let breakouts = crate::multi().min_size(5).fit(&series).unwrap();
+        assert_eq!(vec![10], breakouts);
+    }
+
+    #[test]
+    fn test_bad_min_size() {
         let series = Vec::new();
         let result = crate::multi().min_size(1).fit(&series);
         assert_eq!(
@@ -187,4 +1467,173 @@ mod tests {
             Error::Parameter("degree must be 0, 1, or 2".to_string())
         );
     }
+
+    #[test]
+    fn test_bad_quantile() {
+        let series = Vec::new();
+        let result = crate::multi().quantile(1.5).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("quantile must be between 0 and 1".to_string())
+        );
+
+        let result = crate::multi().quantile(f64::NAN).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("quantile must be between 0 and 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_quantile_mean() {
+        let series = Vec::new();
+        let result = crate::multi()
+            .statistic(Statistic::Mean)
+            .quantile(0.9)
+            .fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("quantile requires median statistic".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_bandwidth() {
+        let series = Vec::new();
+        let result = crate::multi().bandwidth(0.0).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("bandwidth must be positive".to_string())
+        );
+
+        let result = crate::multi().bandwidth(f64::NAN).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("bandwidth must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_anomaly_threshold() {
+        let series = Vec::new();
+        let result = crate::multi().anomaly_threshold(0.0).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("anomaly_threshold must be positive".to_string())
+        );
+
+        let result = crate::multi().anomaly_threshold(f64::NAN).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("anomaly_threshold must be positive".to_string())
+        );
+
+        let result = crate::multi().anomaly_threshold(f64::INFINITY).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("anomaly_threshold must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_min_change() {
+        let series = Vec::new();
+        let result = crate::multi()
+            .min_change(MinChange::Relative(0.0))
+            .fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("min_change must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_significance() {
+        let series = Vec::new();
+        let result = crate::multi().significance(1.0).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("significance must be between 0 and 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_recent() {
+        let series = generate_series();
+        let result = crate::multi().min_size(5).recent(&series, 4);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("k must be at least min_size".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_chunk_size() {
+        let series = Vec::new();
+        let result = crate::multi().min_size(20).chunk_size(100).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("chunk_size must be at least 8 times min_size".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_dispersion() {
+        let series = Vec::new();
+        let result = crate::multi().dispersion(-1.0).fit_counts(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("dispersion must be positive".to_string())
+        );
+
+        let result = crate::multi().dispersion(f64::NAN).fit_counts(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("dispersion must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_seasonality() {
+        let series = Vec::new();
+        let result = crate::multi().seasonality(1).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("seasonality must be at least 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_seasonality_short() {
+        let series = vec![1.0; 40];
+        let result = crate::multi().seasonality(24).fit(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("series must contain at least two periods".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rank_counts() {
+        let result = crate::multi().rank(true).fit_counts(&[1, 2, 3]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("rank cannot be used with counts".to_string())
+        );
+    }
+
+    #[test]
+    fn test_prefilter_counts() {
+        let series = Vec::new();
+        let result = crate::multi()
+            .prefilter(Prefilter::Winsorize {
+                lower: 0.01,
+                upper: 0.99,
+            })
+            .fit_counts(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("prefilter cannot be used with counts".to_string())
+        );
+    }
 }
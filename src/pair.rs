@@ -0,0 +1,216 @@
+use crate::quantile::median;
+use crate::Error;
+
+/// The relationship between two series.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Relation {
+    /// The difference between the series.
+    Difference,
+    /// The ratio of the series.
+    Ratio,
+}
+
+// Denominators this close to zero relative to the typical denominator are dropped,
+// since their ratios would dominate the scale of the relationship
+const TOLERANCE: f64 = 1e-6;
+
+// Returns the relationship of aligned series and the indices of the observations used
+// Observations are dropped when either value is missing or the denominator is near zero
+pub fn derive(a: &[f64], b: &[f64], relation: Relation) -> Result<(Vec<f64>, Vec<usize>), Error> {
+    if a.len() != b.len() {
+        return Err(Error::Parameter(
+            "series must have the same length".to_string(),
+        ));
+    }
+
+    // typical size of the denominator
+    let sizes: Vec<f64> = b
+        .iter()
+        .filter(|v| v.is_finite())
+        .map(|v| v.abs())
+        .collect();
+    let tolerance = if sizes.is_empty() {
+        0.0
+    } else {
+        TOLERANCE * median(&sizes)
+    };
+
+    let mut z = Vec::with_capacity(a.len());
+    let mut kept = Vec::with_capacity(a.len());
+    for (i, (x, y)) in a.iter().zip(b).enumerate() {
+        if !x.is_finite() || !y.is_finite() {
+            continue;
+        }
+        let v = match relation {
+            Relation::Difference => x - y,
+            Relation::Ratio if y.abs() <= tolerance => continue,
+            Relation::Ratio => x / y,
+        };
+        z.push(v);
+        kept.push(i);
+    }
+    Ok((z, kept))
+}
+
+/// The result of detecting breakouts in the relationship between two series.
+#[derive(Debug)]
+pub struct PairAnalysis {
+    breakouts: Vec<usize>,
+    medians: Vec<(f64, f64)>,
+    dropped: Vec<usize>,
+}
+
+impl PairAnalysis {
+    // Maps breakouts in the relationship to the series and summarizes each segment
+    pub(crate) fn new(a: &[f64], b: &[f64], kept: &[usize], breakouts: &[usize]) -> Self {
+        let mut medians = Vec::with_capacity(breakouts.len() + 1);
+        if !kept.is_empty() {
+            let mut start = 0;
+            for end in breakouts.iter().copied().chain([kept.len()]) {
+                let x: Vec<f64> = kept[start..end].iter().map(|i| a[*i]).collect();
+                let y: Vec<f64> = kept[start..end].iter().map(|i| b[*i]).collect();
+                medians.push((median(&x), median(&y)));
+                start = end;
+            }
+        }
+
+        let mut dropped = Vec::with_capacity(a.len() - kept.len());
+        let mut next = kept.iter().peekable();
+        for i in 0..a.len() {
+            if next.peek() == Some(&&i) {
+                next.next();
+            } else {
+                dropped.push(i);
+            }
+        }
+
+        Self {
+            breakouts: breakouts.iter().map(|b| kept[*b]).collect(),
+            medians,
+            dropped,
+        }
+    }
+
+    /// Returns the breakouts.
+    pub fn breakouts(&self) -> &[usize] {
+        &self.breakouts
+    }
+
+    /// Returns the median of each series for each segment.
+    pub fn medians(&self) -> &[(f64, f64)] {
+        &self.medians
+    }
+
+    /// Returns the indices of observations that were missing or had a near-zero denominator.
+    pub fn dropped(&self) -> &[usize] {
+        &self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pair::derive;
+    use crate::{Error, Relation};
+
+    #[rustfmt::skip]
+    fn generate_series() -> Vec<f64> {
+        vec![
+            3.0, 1.0, 2.0, 3.0, 2.0, 1.0, 1.0, 2.0, 2.0, 3.0,
+            6.0, 4.0, 4.0, 5.0, 6.0, 4.0, 4.0, 4.0, 6.0, 5.0,
+            9.0, 8.0, 7.0, 9.0, 8.0, 9.0, 9.0, 9.0, 7.0, 9.0
+        ]
+    }
+
+    // canary that shifts from the baseline at index 15
+    fn generate_canary(baseline: &[f64], shift: f64) -> Vec<f64> {
+        baseline
+            .iter()
+            .enumerate()
+            .map(|(i, v)| if i >= 15 { v * shift } else { *v })
+            .collect()
+    }
+
+    #[test]
+    fn test_derive() {
+        let a = [4.0, 2.0, f64::NAN, 3.0];
+        let b = [2.0, 0.0, 1.0, 1.0];
+        let (z, kept) = derive(&a, &b, Relation::Ratio).unwrap();
+        assert_eq!(vec![2.0, 3.0], z);
+        assert_eq!(vec![0, 3], kept);
+
+        let (z, kept) = derive(&a, &b, Relation::Difference).unwrap();
+        assert_eq!(vec![2.0, 2.0, 2.0], z);
+        assert_eq!(vec![0, 1, 3], kept);
+    }
+
+    #[test]
+    fn test_multi() {
+        let baseline = generate_series();
+        let canary = generate_canary(&baseline, 2.0);
+        let analysis = crate::multi()
+            .min_size(5)
+            .analyze_pair(&canary, &baseline, Relation::Ratio)
+            .unwrap();
+        assert_eq!(&[15], analysis.breakouts());
+        assert_eq!(&[(3.0, 3.0), (16.0, 8.0)], analysis.medians());
+        assert!(analysis.dropped().is_empty());
+    }
+
+    #[test]
+    fn test_zero_denominator() {
+        let mut baseline = generate_series();
+        baseline[3] = 0.0;
+        let canary = generate_canary(&baseline, 2.0);
+        let analysis = crate::multi()
+            .min_size(5)
+            .analyze_pair(&canary, &baseline, Relation::Ratio)
+            .unwrap();
+        assert_eq!(&[15], analysis.breakouts());
+        assert_eq!(&[3], analysis.dropped());
+    }
+
+    #[test]
+    fn test_near_zero_denominator() {
+        let mut baseline = generate_series();
+        baseline[3] = 1e-9;
+        let canary = generate_canary(&baseline, 2.0);
+        let analysis = crate::multi()
+            .min_size(5)
+            .analyze_pair(&canary, &baseline, Relation::Ratio)
+            .unwrap();
+        assert_eq!(&[15], analysis.breakouts());
+        assert_eq!(&[3], analysis.dropped());
+    }
+
+    #[test]
+    fn test_amoc() {
+        let baseline = generate_series();
+        let canary = generate_canary(&baseline, 2.0);
+        let analysis = crate::amoc()
+            .min_size(5)
+            .analyze_pair(&canary, &baseline, Relation::Ratio)
+            .unwrap();
+        assert_eq!(&[15], analysis.breakouts());
+        assert_eq!(2, analysis.medians().len());
+    }
+
+    #[test]
+    fn test_no_breakouts() {
+        let baseline = generate_series();
+        let analysis = crate::multi()
+            .min_size(5)
+            .analyze_pair(&baseline, &baseline, Relation::Difference)
+            .unwrap();
+        assert!(analysis.breakouts().is_empty());
+        assert_eq!(&[(4.5, 4.5)], analysis.medians());
+    }
+
+    #[test]
+    fn test_bad_length() {
+        let result = crate::multi().analyze_pair(&[1.0, 2.0], &[1.0], Relation::Difference);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("series must have the same length".to_string())
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::divergence::Divergence;
+
+// Average of the largest divergences across aligned series
+// Averaging only the largest ones lets breakouts shared by a fraction of the series stand out
+pub struct Panel<'a> {
+    series: Vec<Box<dyn Divergence + 'a>>,
+    count: usize,
+    values: Vec<f64>,
+}
+
+impl<'a> Panel<'a> {
+    pub fn new(series: Vec<Box<dyn Divergence + 'a>>, count: usize) -> Self {
+        let values = Vec::with_capacity(series.len());
+        Self {
+            series,
+            count,
+            values,
+        }
+    }
+
+    // Returns the divergence of each series
+    pub fn each(&mut self, a: usize, t: usize, s: usize) -> Vec<f64> {
+        self.series.iter_mut().map(|d| d.between(a, t, s)).collect()
+    }
+}
+
+impl Divergence for Panel<'_> {
+    fn between(&mut self, a: usize, t: usize, s: usize) -> f64 {
+        self.values.clear();
+        for d in &mut self.series {
+            self.values.push(d.between(a, t, s));
+        }
+        if self.count < self.values.len() {
+            self.values
+                .select_nth_unstable_by(self.count - 1, |x, y| y.partial_cmp(x).unwrap());
+        }
+        self.values[..self.count].iter().sum::<f64>() / self.count as f64
+    }
+}
+
+/// The result of detecting breakouts shared by multiple series.
+#[derive(Debug)]
+pub struct PanelAnalysis {
+    pub(crate) breakouts: Vec<usize>,
+    pub(crate) participants: Vec<Vec<usize>>,
+}
+
+impl PanelAnalysis {
+    /// Returns the breakouts.
+    pub fn breakouts(&self) -> &[usize] {
+        &self.breakouts
+    }
+
+    /// Returns the indices of the series that participated in each breakout.
+    pub fn participants(&self) -> &[Vec<usize>] {
+        &self.participants
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::divergence::{Divergence, Means};
+    use crate::panel::Panel;
+    use crate::Error;
+
+    #[rustfmt::skip]
+    fn generate_series() -> Vec<f64> {
+        vec![
+            3.0, 1.0, 2.0, 3.0, 2.0, 1.0, 1.0, 2.0, 2.0, 3.0,
+            6.0, 4.0, 4.0, 5.0, 6.0, 4.0, 4.0, 4.0, 6.0, 5.0,
+            9.0, 8.0, 7.0, 9.0, 8.0, 9.0, 9.0, 9.0, 7.0, 9.0
+        ]
+    }
+
+    fn flat() -> Vec<f64> {
+        (0..30).map(|i| [1.0, 3.0, 2.0][i % 3]).collect()
+    }
+
+    #[test]
+    fn test_panel() {
+        let z1 = [0.0, 0.0, 1.0, 1.0];
+        let z2 = [0.0, 0.0, 0.0, 2.0];
+        let mut panel = Panel::new(
+            vec![Box::new(Means::new(&z1)), Box::new(Means::new(&z2))],
+            1,
+        );
+        assert_eq!(1.0, panel.between(0, 2, 4));
+        assert_eq!(vec![1.0, 1.0], panel.each(0, 2, 4));
+
+        let mut panel = Panel::new(
+            vec![Box::new(Means::new(&z1)), Box::new(Means::new(&z2))],
+            2,
+        );
+        assert!((panel.between(0, 3, 4) - 20.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_shared() {
+        let series = vec![generate_series(), generate_series(), flat()];
+        let analysis = crate::multi()
+            .min_size(5)
+            .panel_fraction(0.5)
+            .analyze_panel(&series)
+            .unwrap();
+        assert_eq!(&[10, 15, 20], analysis.breakouts());
+        assert_eq!(
+            &[vec![0, 1], vec![0, 1], vec![0, 1]],
+            analysis.participants()
+        );
+    }
+
+    #[test]
+    fn test_fraction() {
+        // a breakout in a single series isn't shared by the panel
+        let series = vec![generate_series(), flat(), flat(), flat()];
+        let analysis = crate::multi().min_size(5).analyze_panel(&series).unwrap();
+        assert!(analysis.breakouts().is_empty());
+        assert!(analysis.participants().is_empty());
+
+        let analysis = crate::multi()
+            .min_size(5)
+            .panel_fraction(0.25)
+            .analyze_panel(&series)
+            .unwrap();
+        assert_eq!(&[10, 15, 20], analysis.breakouts());
+    }
+
+    #[test]
+    fn test_empty() {
+        let series: Vec<Vec<f64>> = Vec::new();
+        let analysis = crate::multi().analyze_panel(&series).unwrap();
+        assert!(analysis.breakouts().is_empty());
+    }
+
+    #[test]
+    fn test_bad_length() {
+        let series = vec![generate_series(), vec![1.0]];
+        let result = crate::multi().analyze_panel(&series);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("series must have the same length".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_panel_fraction() {
+        let result = crate::multi()
+            .panel_fraction(0.0)
+            .analyze_panel(&[generate_series()]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("panel_fraction must be between 0 and 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let result = crate::multi()
+            .confidence(0.9)
+            .analyze_panel(&[generate_series()]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("confidence cannot be used with panel".to_string())
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::quantile::{mad, median, quantile};
+use crate::Error;
+
+/// A filter for outliers applied before detection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Prefilter {
+    /// Replaces observations more than `threshold` scaled median absolute deviations
+    /// from the median of the surrounding `window` observations with that median.
+    Hampel { window: usize, threshold: f64 },
+    /// Clamps observations to the `lower` and `upper` quantiles.
+    Winsorize { lower: f64, upper: f64 },
+}
+
+impl Prefilter {
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        match *self {
+            Prefilter::Hampel { window, threshold } => {
+                if window < 3 {
+                    return Err(Error::Parameter("window must be at least 3".to_string()));
+                }
+                if threshold <= 0.0 {
+                    return Err(Error::Parameter("threshold must be positive".to_string()));
+                }
+            }
+            Prefilter::Winsorize { lower, upper } => {
+                if lower < 0.0 || upper > 1.0 || lower >= upper {
+                    return Err(Error::Parameter(
+                        "lower and upper must satisfy 0 <= lower < upper <= 1".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the filtered series and the indices of modified observations
+    pub(crate) fn apply(&self, z: &[f64]) -> (Vec<f64>, Vec<usize>) {
+        match *self {
+            Prefilter::Hampel { window, threshold } => hampel(z, window, threshold),
+            Prefilter::Winsorize { lower, upper } => winsorize(z, lower, upper),
+        }
+    }
+}
+
+fn hampel(z: &[f64], window: usize, threshold: f64) -> (Vec<f64>, Vec<usize>) {
+    let n = z.len();
+    let window = window.min(n);
+    let mut filtered = z.to_vec();
+    let mut modified = Vec::new();
+    for i in 0..n {
+        let start = i.saturating_sub(window / 2).min(n - window);
+        let values = &z[start..start + window];
+        let m = median(values);
+        if (z[i] - m).abs() > threshold * mad(values, m) {
+            filtered[i] = m;
+            modified.push(i);
+        }
+    }
+    (filtered, modified)
+}
+
+fn winsorize(z: &[f64], lower: f64, upper: f64) -> (Vec<f64>, Vec<usize>) {
+    let lo = quantile(z, lower);
+    let hi = quantile(z, upper);
+    let mut modified = Vec::new();
+    let filtered = z
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let c = v.clamp(lo, hi);
+            if c != *v {
+                modified.push(i);
+            }
+            c
+        })
+        .collect();
+    (filtered, modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Prefilter;
+
+    #[test]
+    fn test_hampel() {
+        let mut series = vec![1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 1.0, 2.0];
+        series[4] = 50.0;
+        let filter = Prefilter::Hampel {
+            window: 5,
+            threshold: 3.0,
+        };
+        let (filtered, modified) = filter.apply(&series);
+        assert_eq!(modified, vec![4]);
+        assert_eq!(filtered[4], 2.0);
+    }
+
+    #[test]
+    fn test_hampel_constant() {
+        let mut series = vec![1.0; 10];
+        series[6] = 5.0;
+        let filter = Prefilter::Hampel {
+            window: 5,
+            threshold: 3.0,
+        };
+        let (filtered, modified) = filter.apply(&series);
+        assert_eq!(modified, vec![6]);
+        assert_eq!(filtered[6], 1.0);
+    }
+
+    #[test]
+    fn test_winsorize() {
+        let series: Vec<f64> = (0..11).map(|i| i as f64).collect();
+        let filter = Prefilter::Winsorize {
+            lower: 0.1,
+            upper: 0.9,
+        };
+        let (filtered, modified) = filter.apply(&series);
+        assert_eq!(modified, vec![0, 10]);
+        assert_eq!(filtered[0], 1.0);
+        assert_eq!(filtered[10], 9.0);
+    }
+}
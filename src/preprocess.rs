@@ -0,0 +1,130 @@
+// Transformations applied to a series before detection
+
+use crate::prewhiten::prewhiten;
+use crate::rank_sum::midranks;
+use crate::seasonality::seasonal;
+use crate::{Error, Prefilter, Trend};
+
+pub struct Prepared {
+    // series used for detection
+    pub z: Vec<f64>,
+    // original series with seasonality and trend removed, but not outliers or autocorrelation, for anomalies
+    pub adjusted: Vec<f64>,
+    // indices of observations modified by the prefilter
+    pub filtered: Vec<usize>,
+}
+
+pub struct Preprocess {
+    pub prefilter: Option<Prefilter>,
+    pub seasonality: Option<usize>,
+    pub detrend: Option<Trend>,
+    pub prewhiten: bool,
+    pub rank: bool,
+}
+
+impl Preprocess {
+    pub fn new() -> Self {
+        Self {
+            prefilter: None,
+            seasonality: None,
+            detrend: None,
+            prewhiten: false,
+            rank: false,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(prefilter) = &self.prefilter {
+            prefilter.validate()?;
+        }
+        if let Some(period) = self.seasonality {
+            if period < 2 {
+                return Err(Error::Parameter(
+                    "seasonality must be at least 2".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Returns an error naming the first option set, for inputs that cannot be transformed
+    pub fn unsupported(&self, input: &str) -> Result<(), Error> {
+        let option = if self.prefilter.is_some() {
+            "prefilter"
+        } else if self.seasonality.is_some() {
+            "seasonality"
+        } else if self.detrend.is_some() {
+            "detrend"
+        } else if self.prewhiten {
+            "prewhiten"
+        } else if self.rank {
+            "rank"
+        } else {
+            return Ok(());
+        };
+        Err(Error::Parameter(format!(
+            "{} cannot be used with {}",
+            option, input
+        )))
+    }
+
+    pub fn apply(&self, z: &[f64], min_size: usize) -> Result<Prepared, Error> {
+        let mut adjusted = z.to_vec();
+
+        // remove outliers
+        let (mut z, filtered) = match &self.prefilter {
+            Some(prefilter) => prefilter.apply(z),
+            None => (z.to_vec(), Vec::new()),
+        };
+
+        // remove seasonality
+        if let Some(period) = self.seasonality {
+            if z.len() < 2 * period {
+                return Err(Error::Parameter(
+                    "series must contain at least two periods".to_string(),
+                ));
+            }
+            let component = seasonal(&z, period);
+            for i in 0..z.len() {
+                z[i] -= component[i % period];
+                adjusted[i] -= component[i % period];
+            }
+        }
+
+        // remove trend
+        if let Some(trend) = self.detrend {
+            let slope = trend.slope(&z);
+            for i in 0..z.len() {
+                z[i] -= slope * i as f64;
+                adjusted[i] -= slope * i as f64;
+            }
+        }
+
+        // remove autocorrelation
+        if self.prewhiten {
+            z = prewhiten(&z, (2 * min_size).min(z.len()));
+        }
+
+        // replace observations with ranks, so only their order matters
+        if self.rank {
+            z = midranks(&z);
+        }
+
+        Ok(Prepared {
+            z,
+            adjusted,
+            filtered,
+        })
+    }
+}
+
+// Scales observations to [0, 1] and returns the range, or None for an empty or constant series
+pub fn scale(z: &[f64]) -> Option<(Vec<f64>, f64)> {
+    let min = z.iter().min_by(|i, j| i.partial_cmp(j).unwrap())?;
+    let max = z.iter().max_by(|i, j| i.partial_cmp(j).unwrap())?;
+    let denom = max - min;
+    if denom == 0.0 {
+        return None;
+    }
+    Some((z.iter().map(|x| (x - min) / denom).collect(), denom))
+}
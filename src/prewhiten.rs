@@ -0,0 +1,126 @@
+// Autoregressive prewhitening
+// Coefficients are estimated from deviations from a moving median, so level shifts
+// are not mistaken for autocorrelation, and the order is selected with AIC
+
+use crate::quantile::moving_median;
+
+// maximum order of the autoregressive model
+const MAX_ORDER: usize = 10;
+
+fn autocovariances(x: &[f64], max_lag: usize) -> Vec<f64> {
+    let n = x.len();
+    let mean = x.iter().sum::<f64>() / n as f64;
+    (0..=max_lag)
+        .map(|k| {
+            let mut sum = 0.0;
+            for i in k..n {
+                sum += (x[i] - mean) * (x[i - k] - mean);
+            }
+            sum / n as f64
+        })
+        .collect()
+}
+
+// Solves the Yule-Walker equations for each order with the Levinson-Durbin recursion
+// Returns the coefficients and innovation variance for each order
+fn levinson_durbin(acov: &[f64]) -> Vec<(Vec<f64>, f64)> {
+    let mut models = vec![(Vec::new(), acov[0])];
+    let mut phi: Vec<f64> = Vec::new();
+    let mut var = acov[0];
+    for p in 1..acov.len() {
+        if var <= 0.0 {
+            break;
+        }
+        let mut k = acov[p];
+        for j in 0..p - 1 {
+            k -= phi[j] * acov[p - 1 - j];
+        }
+        k /= var;
+
+        let mut next = Vec::with_capacity(p);
+        for j in 0..p - 1 {
+            next.push(phi[j] - k * phi[p - 2 - j]);
+        }
+        next.push(k);
+        phi = next;
+        var *= 1.0 - k * k;
+        models.push((phi.clone(), var));
+    }
+    models
+}
+
+// Returns the coefficients of the model with the lowest AIC
+pub fn select_order(z: &[f64], window: usize) -> Vec<f64> {
+    let n = z.len();
+    let residuals: Vec<f64> = z
+        .iter()
+        .zip(moving_median(z, window))
+        .map(|(v, m)| v - m)
+        .collect();
+
+    let max_order = MAX_ORDER.min(n / 4);
+    let acov = autocovariances(&residuals, max_order);
+    if acov[0] == 0.0 {
+        return Vec::new();
+    }
+
+    let mut best = Vec::new();
+    let mut best_aic = f64::INFINITY;
+    for (phi, var) in levinson_durbin(&acov) {
+        if var <= 0.0 {
+            break;
+        }
+        let aic = n as f64 * var.ln() + 2.0 * phi.len() as f64;
+        if aic < best_aic {
+            best_aic = aic;
+            best = phi;
+        }
+    }
+    best
+}
+
+// Removes autocorrelation, using the first observation before the start of the series
+pub fn prewhiten(z: &[f64], window: usize) -> Vec<f64> {
+    let phi = select_order(z, window);
+    (0..z.len())
+        .map(|t| {
+            let mut e = z[t];
+            for (k, c) in phi.iter().enumerate() {
+                e -= c * z[t.saturating_sub(k + 1)];
+            }
+            e
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prewhiten::{levinson_durbin, select_order};
+
+    #[test]
+    fn test_levinson_durbin() {
+        // autocovariances of an AR(1) process with phi = 0.6
+        let acov = [1.0, 0.6, 0.36, 0.216];
+        let models = levinson_durbin(&acov);
+        assert!((models[1].0[0] - 0.6).abs() < 1e-12);
+        assert!((models[1].1 - 0.64).abs() < 1e-12);
+        assert!(models[2].0[1].abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_select_order() {
+        let mut seed: u64 = 1;
+        let mut x = 0.0;
+        let series: Vec<f64> = (0..500)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let noise = (seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+                x = 0.7 * x + noise;
+                x
+            })
+            .collect();
+        let phi = select_order(&series, 30);
+        assert!(!phi.is_empty());
+        assert!(phi[0] > 0.4);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::divergence::{Tracker, Window};
+use crate::multiset::Multiset;
+use crate::ranks::RankQuantile;
+use std::cmp::Ordering;
+
+#[derive(PartialEq)]
+struct MaxItem(f64);
+
+impl Eq for MaxItem {}
+
+impl PartialOrd for MaxItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MaxItem {
+    fn cmp(&self, other: &MaxItem) -> Ordering {
+        other.0.partial_cmp(&self.0).unwrap()
+    }
+}
+
+#[derive(PartialEq)]
+struct MinItem(f64);
+
+impl Eq for MinItem {}
+
+impl PartialOrd for MinItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MinItem {
+    fn cmp(&self, other: &MinItem) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap()
+    }
+}
+
+// Median of a collection of observations
+pub fn median(x: &[f64]) -> f64 {
+    let odd = x.len() % 2 == 1;
+    let mut x = x.to_vec();
+    let mid = x.len() / 2;
+    let (lower, m, _) = x.select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap());
+    let m = *m;
+    if odd {
+        m
+    } else {
+        let lo = lower.iter().fold(f64::NEG_INFINITY, |a, b| a.max(*b));
+        (lo + m) / 2.0
+    }
+}
+
+// Median absolute deviation from a center, scaled to be consistent for normal data
+// Uses the mean absolute deviation when most observations are tied
+pub fn mad(x: &[f64], center: f64) -> f64 {
+    let deviations: Vec<f64> = x.iter().map(|v| (v - center).abs()).collect();
+    let scale = 1.4826 * median(&deviations);
+    if scale == 0.0 {
+        1.2533 * deviations.iter().sum::<f64>() / deviations.len() as f64
+    } else {
+        scale
+    }
+}
+
+// Quantile of a collection of observations, interpolating between order statistics
+pub fn quantile(x: &[f64], q: f64) -> f64 {
+    let mut x = x.to_vec();
+    x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let h = (x.len() - 1) as f64 * q;
+    let i = h.floor() as usize;
+    if i + 1 < x.len() {
+        (1.0 - h.fract()) * x[i] + h.fract() * x[i + 1]
+    } else {
+        x[i]
+    }
+}
+
+// Centered moving median, with windows shifted inward at the ends
+// The series must contain at least one window
+pub fn moving_median(z: &[f64], width: usize) -> Vec<f64> {
+    let n = z.len();
+    let mut window = Window::new(RankQuantile::new(z, 0.5));
+    let mut medians = Vec::with_capacity(n);
+    for i in 0..n {
+        let start = i.saturating_sub(width / 2).min(n - width);
+        window.set(z, start, start + width);
+        medians.push(window.value());
+    }
+    medians
+}
+
+// Sliding quantile using a pair of trees
+// Uses linear interpolation between order statistics, so q = 0.5 is the usual median
+pub struct Quantile {
+    q: f64,
+    // tree used to store the observations above the quantile
+    upper: Multiset<MinItem>,
+    // tree used to store the observations at or below the quantile
+    lower: Multiset<MaxItem>,
+}
+
+impl Quantile {
+    pub fn new(q: f64) -> Self {
+        Self {
+            q,
+            upper: Multiset::new(),
+            lower: Multiset::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.upper.len() + self.lower.len()
+    }
+
+    // position of the quantile in the sorted observations
+    fn position(&self) -> f64 {
+        (self.len() - 1) as f64 * self.q
+    }
+
+    // move observations between trees so the lower tree ends at the quantile
+    fn rebalance(&mut self) {
+        let target = if self.len() == 0 {
+            0
+        } else {
+            self.position().floor() as usize + 1
+        };
+        while self.lower.len() > target {
+            let i = self.lower.first().unwrap().0;
+            self.upper.insert(MinItem(i));
+            self.lower.remove(MaxItem(i));
+        }
+        while self.lower.len() < target {
+            let i = self.upper.first().unwrap().0;
+            self.lower.insert(MaxItem(i));
+            self.upper.remove(MinItem(i));
+        }
+    }
+}
+
+impl Tracker for Quantile {
+    fn insert(&mut self, x: f64) {
+        if self.upper.is_empty() || x < self.upper.first().unwrap().0 {
+            self.lower.insert(MaxItem(x));
+        } else {
+            self.upper.insert(MinItem(x));
+        }
+        self.rebalance();
+    }
+
+    fn remove(&mut self, x: f64) {
+        if self.upper.is_empty() || x < self.upper.first().unwrap().0 {
+            self.lower.remove(MaxItem(x));
+        } else {
+            self.upper.remove(MinItem(x));
+        }
+        self.rebalance();
+    }
+
+    fn clear(&mut self) {
+        self.upper.clear();
+        self.lower.clear();
+    }
+
+    fn value(&self) -> f64 {
+        let lo = self.lower.first().unwrap().0;
+        let frac = self.position().fract();
+        if frac == 0.0 {
+            lo
+        } else {
+            let hi = self.upper.first().unwrap().0;
+            (1.0 - frac) * lo + frac * hi
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::divergence::Tracker;
+    use crate::quantile::{median, quantile, Quantile};
+
+    fn naive(x: &[f64], q: f64) -> f64 {
+        let mut x = x.to_vec();
+        x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let h = (x.len() - 1) as f64 * q;
+        let i = h.floor() as usize;
+        if i + 1 < x.len() {
+            x[i] + (h - i as f64) * (x[i + 1] - x[i])
+        } else {
+            x[i]
+        }
+    }
+
+    #[test]
+    fn test_works() {
+        let values = [5.0, 1.0, 4.0, 4.0, 2.0, 9.0, 3.0, 7.0];
+        for q in [0.0, 0.1, 0.25, 0.5, 0.9, 0.95, 1.0] {
+            let mut quantile = Quantile::new(q);
+            for (i, v) in values.iter().enumerate() {
+                quantile.insert(*v);
+                assert!((quantile.value() - naive(&values[..=i], q)).abs() < 1e-12);
+            }
+            for i in 0..values.len() - 1 {
+                quantile.remove(values[i]);
+                assert!((quantile.value() - naive(&values[i + 1..], q)).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+    }
+
+    #[test]
+    fn test_quantile() {
+        let values = [5.0, 1.0, 4.0, 4.0, 2.0, 9.0, 3.0, 7.0];
+        for q in [0.0, 0.1, 0.25, 0.5, 0.9, 1.0] {
+            assert!((quantile(&values, q) - naive(&values, q)).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// Wilcoxon rank-sum test with a normal approximation and a correction for ties
+
+// Returns the two-sided p-value for a difference in location between two samples
+pub fn rank_sum(x: &[f64], y: &[f64]) -> f64 {
+    let n1 = x.len() as f64;
+    let n2 = y.len() as f64;
+    let n = n1 + n2;
+
+    let mut values: Vec<(f64, bool)> = x
+        .iter()
+        .map(|v| (*v, true))
+        .chain(y.iter().map(|v| (*v, false)))
+        .collect();
+    values.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    // sum the midranks of x and the tie correction
+    let mut w = 0.0;
+    let mut ties = 0.0;
+    let mut i = 0;
+    while i < values.len() {
+        let mut j = i;
+        while j < values.len() && values[j].0 == values[i].0 {
+            j += 1;
+        }
+        let rank = (i + j + 1) as f64 / 2.0;
+        w += rank * values[i..j].iter().filter(|v| v.1).count() as f64;
+        let t = (j - i) as f64;
+        ties += t * t * t - t;
+        i = j;
+    }
+
+    let mean = n1 * (n + 1.0) / 2.0;
+    let var = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if var <= 0.0 {
+        return 1.0;
+    }
+    let z = (w - mean).abs() / var.sqrt();
+    erfc(z / std::f64::consts::SQRT_2)
+}
+
+// Returns the rank of each observation, using the average rank for ties
+pub fn midranks(z: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..z.len()).collect();
+    order.sort_by(|a, b| z[*a].partial_cmp(&z[*b]).unwrap());
+
+    let mut ranks = vec![0.0; z.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j < order.len() && z[order[j]] == z[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j + 1) as f64 / 2.0;
+        for k in i..j {
+            ranks[order[k]] = rank;
+        }
+        i = j;
+    }
+    ranks
+}
+
+// Complementary error function with fractional error less than 1.2e-7
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let ans = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        ans
+    } else {
+        2.0 - ans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rank_sum::{erfc, midranks, rank_sum};
+
+    #[test]
+    fn test_erfc() {
+        assert!((erfc(0.0) - 1.0).abs() < 1e-7);
+        assert!((erfc(1.0) - 0.157299207050285).abs() < 1e-7);
+        assert!((erfc(-1.0) - 1.842700792949715).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_rank_sum() {
+        let p = rank_sum(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0]);
+        assert!((p - 0.009023438).abs() < 1e-6);
+
+        let p = rank_sum(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]);
+        assert!((p - 1.0).abs() < 1e-6);
+
+        assert_eq!(rank_sum(&[1.0, 1.0], &[1.0, 1.0]), 1.0);
+    }
+
+    #[test]
+    fn test_midranks() {
+        let ranks = midranks(&[3.0, 1.0, 4.0, 1.0, 5.0]);
+        assert_eq!(vec![3.0, 1.5, 4.0, 1.5, 5.0], ranks);
+    }
+}
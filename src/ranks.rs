@@ -0,0 +1,212 @@
+// Sliding quantiles of observations from a series known up front
+// Counts are kept in a Fenwick tree over the ranks of the distinct values, so inserting,
+// removing, and finding the k-th observation take O(log n) time without allocations
+
+use crate::divergence::Tracker;
+
+pub struct Ranks {
+    // distinct values in ascending order
+    values: Vec<f64>,
+    // Fenwick tree of counts, indexed from 1
+    tree: Vec<u32>,
+    // largest power of two not greater than the number of values
+    top: usize,
+    len: usize,
+}
+
+impl Ranks {
+    pub fn new(z: &[f64]) -> Self {
+        let mut values = z.to_vec();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.dedup();
+        let m = values.len();
+        let top = if m == 0 {
+            0
+        } else {
+            1 << (usize::BITS - 1 - m.leading_zeros())
+        };
+        Self {
+            values,
+            tree: vec![0; m + 1],
+            top,
+            len: 0,
+        }
+    }
+
+    fn add(&mut self, x: f64, delta: i32) {
+        // observations must come from the series
+        let mut i = self.values.partition_point(|v| *v < x) + 1;
+        while i < self.tree.len() {
+            self.tree[i] = self.tree[i].wrapping_add(delta as u32);
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    pub fn insert(&mut self, x: f64) {
+        self.add(x, 1);
+        self.len += 1;
+    }
+
+    pub fn remove(&mut self, x: f64) {
+        self.add(x, -1);
+        self.len -= 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.tree.fill(0);
+        self.len = 0;
+    }
+
+    // Returns the observation with rank k (from 0)
+    fn kth(&self, k: usize) -> f64 {
+        let mut pos = 0;
+        let mut rem = k as u32;
+        let mut step = self.top;
+        while step > 0 {
+            if pos + step < self.tree.len() && self.tree[pos + step] <= rem {
+                pos += step;
+                rem -= self.tree[pos];
+            }
+            step /= 2;
+        }
+        self.values[pos]
+    }
+
+    // Uses linear interpolation between order statistics, like Quantile
+    pub fn quantile(&self, q: f64) -> f64 {
+        let position = (self.len - 1) as f64 * q;
+        let i = position.floor() as usize;
+        let lo = self.kth(i);
+        let frac = position.fract();
+        if frac == 0.0 {
+            lo
+        } else {
+            let hi = self.kth(i + 1);
+            (1.0 - frac) * lo + frac * hi
+        }
+    }
+}
+
+// Sliding quantile of observations from a series
+pub struct RankQuantile {
+    ranks: Ranks,
+    q: f64,
+}
+
+impl RankQuantile {
+    pub fn new(z: &[f64], q: f64) -> Self {
+        Self {
+            ranks: Ranks::new(z),
+            q,
+        }
+    }
+}
+
+impl Tracker for RankQuantile {
+    fn insert(&mut self, x: f64) {
+        self.ranks.insert(x);
+    }
+
+    fn remove(&mut self, x: f64) {
+        self.ranks.remove(x);
+    }
+
+    fn clear(&mut self) {
+        self.ranks.clear();
+    }
+
+    fn value(&self) -> f64 {
+        self.ranks.quantile(self.q)
+    }
+}
+
+// Sliding interquartile range of observations from a series
+pub struct RankInterquartileRange {
+    ranks: Ranks,
+}
+
+impl RankInterquartileRange {
+    pub fn new(z: &[f64]) -> Self {
+        Self {
+            ranks: Ranks::new(z),
+        }
+    }
+}
+
+impl Tracker for RankInterquartileRange {
+    fn insert(&mut self, x: f64) {
+        self.ranks.insert(x);
+    }
+
+    fn remove(&mut self, x: f64) {
+        self.ranks.remove(x);
+    }
+
+    fn clear(&mut self) {
+        self.ranks.clear();
+    }
+
+    fn value(&self) -> f64 {
+        self.ranks.quantile(0.75) - self.ranks.quantile(0.25)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::divergence::Tracker;
+    use crate::quantile::Quantile;
+    use crate::ranks::{RankInterquartileRange, RankQuantile, Ranks};
+
+    fn generate_series() -> Vec<f64> {
+        let mut seed: u64 = 1;
+        (0..300)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                ((seed >> 33) % 40) as f64 / 4.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_quantile() {
+        // same values as the tree-based quantile, including ties
+        let series = generate_series();
+        for q in [0.0, 0.1, 0.25, 0.5, 0.9, 1.0] {
+            let mut ranks = RankQuantile::new(&series, q);
+            let mut quantile = Quantile::new(q);
+            for i in 0..series.len() {
+                ranks.insert(series[i]);
+                quantile.insert(series[i]);
+                if i >= 25 {
+                    ranks.remove(series[i - 25]);
+                    quantile.remove(series[i - 25]);
+                }
+                assert_eq!(quantile.value(), ranks.value());
+            }
+        }
+    }
+
+    #[test]
+    fn test_interquartile_range() {
+        let values = [5.0, 1.0, 4.0, 4.0, 2.0, 9.0, 3.0, 7.0];
+        let mut iqr = RankInterquartileRange::new(&values);
+        for v in values {
+            iqr.insert(v);
+        }
+        assert_eq!(2.75, iqr.value());
+
+        iqr.remove(9.0);
+        assert_eq!(2.0, iqr.value());
+    }
+
+    #[test]
+    fn test_clear() {
+        let values = [3.0, 1.0, 2.0];
+        let mut ranks = Ranks::new(&values);
+        ranks.insert(3.0);
+        ranks.insert(2.0);
+        ranks.clear();
+        ranks.insert(1.0);
+        assert_eq!(1.0, ranks.quantile(0.5));
+    }
+}
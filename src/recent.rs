@@ -0,0 +1,22 @@
+/// A breakout in the last observations of a series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recent {
+    index: usize,
+    statistic: f64,
+}
+
+impl Recent {
+    pub(crate) fn new(index: usize, statistic: f64) -> Self {
+        Self { index, statistic }
+    }
+
+    /// Returns the index of the breakout.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the statistic for the breakout, which measures its strength.
+    pub fn statistic(&self) -> f64 {
+        self.statistic
+    }
+}
@@ -0,0 +1,211 @@
+use crate::divergence::Tracker;
+use crate::quantile::{moving_median, Quantile};
+use crate::Error;
+
+/// A quantile of a collection of observations that supports insertion and removal.
+///
+/// Uses linear interpolation between order statistics. Observations must not be NaN.
+pub struct RollingQuantile {
+    // an ordered multiset, since ranks for a Fenwick tree need all values up front
+    quantile: Quantile,
+}
+
+impl RollingQuantile {
+    /// Creates a new rolling quantile.
+    pub fn new(q: f64) -> Result<Self, Error> {
+        if !(0.0..=1.0).contains(&q) {
+            return Err(Error::Parameter("q must be between 0 and 1".to_string()));
+        }
+        Ok(Self {
+            quantile: Quantile::new(q),
+        })
+    }
+
+    /// Returns the number of observations.
+    pub fn len(&self) -> usize {
+        self.quantile.len()
+    }
+
+    /// Returns whether there are no observations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Inserts an observation.
+    pub fn insert(&mut self, x: f64) {
+        self.quantile.insert(x);
+    }
+
+    /// Removes an observation, returning whether it was present.
+    pub fn remove(&mut self, x: f64) -> bool {
+        let len = self.len();
+        self.quantile.remove(x);
+        self.len() < len
+    }
+
+    /// Removes all observations.
+    pub fn clear(&mut self) {
+        self.quantile.clear();
+    }
+
+    /// Returns the quantile, or `None` when there are no observations.
+    pub fn quantile(&self) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.quantile.value())
+        }
+    }
+}
+
+/// A median of a collection of observations that supports insertion and removal.
+pub struct RollingMedian {
+    quantile: RollingQuantile,
+}
+
+impl RollingMedian {
+    /// Creates a new rolling median.
+    pub fn new() -> Self {
+        Self {
+            quantile: RollingQuantile {
+                quantile: Quantile::new(0.5),
+            },
+        }
+    }
+
+    /// Returns the number of observations.
+    pub fn len(&self) -> usize {
+        self.quantile.len()
+    }
+
+    /// Returns whether there are no observations.
+    pub fn is_empty(&self) -> bool {
+        self.quantile.is_empty()
+    }
+
+    /// Inserts an observation.
+    pub fn insert(&mut self, x: f64) {
+        self.quantile.insert(x);
+    }
+
+    /// Removes an observation, returning whether it was present.
+    pub fn remove(&mut self, x: f64) -> bool {
+        self.quantile.remove(x)
+    }
+
+    /// Removes all observations.
+    pub fn clear(&mut self) {
+        self.quantile.clear();
+    }
+
+    /// Returns the median, or `None` when there are no observations.
+    pub fn median(&self) -> Option<f64> {
+        self.quantile.quantile()
+    }
+}
+
+impl Default for RollingMedian {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the centered rolling median of a series.
+///
+/// Windows are shifted inward at the ends of the series, so each median uses `window`
+/// observations. The window is limited to the length of the series.
+pub fn rolling_median(series: &[f64], window: usize) -> Vec<f64> {
+    if series.is_empty() {
+        return Vec::new();
+    }
+    moving_median(series, window.clamp(1, series.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rolling_median, Error, RollingMedian, RollingQuantile};
+
+    fn naive(x: &[f64], q: f64) -> f64 {
+        let mut x = x.to_vec();
+        x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let h = (x.len() - 1) as f64 * q;
+        let i = h.floor() as usize;
+        if i + 1 < x.len() {
+            x[i] + (h - i as f64) * (x[i + 1] - x[i])
+        } else {
+            x[i]
+        }
+    }
+
+    fn generate_series() -> Vec<f64> {
+        let mut seed: u64 = 1;
+        (0..200)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                ((seed >> 33) % 50) as f64
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_quantile() {
+        let series = generate_series();
+        for q in [0.0, 0.1, 0.5, 0.9, 1.0] {
+            let mut quantile = RollingQuantile::new(q).unwrap();
+            for i in 0..series.len() {
+                quantile.insert(series[i]);
+                if i >= 10 {
+                    assert!(quantile.remove(series[i - 10]));
+                }
+                let start = i.saturating_sub(9);
+                let expected = naive(&series[start..=i], q);
+                assert!((quantile.quantile().unwrap() - expected).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bad_quantile() {
+        for q in [-0.1, 1.1, f64::NAN] {
+            let result = RollingQuantile::new(q);
+            assert_eq!(
+                result.err(),
+                Some(Error::Parameter("q must be between 0 and 1".to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_median() {
+        let mut median = RollingMedian::new();
+        assert_eq!(None, median.median());
+        median.insert(3.0);
+        median.insert(1.0);
+        assert_eq!(Some(2.0), median.median());
+        median.insert(2.0);
+        assert_eq!(Some(2.0), median.median());
+        assert!(!median.remove(5.0));
+        assert!(median.remove(1.0));
+        assert_eq!(Some(2.5), median.median());
+        assert_eq!(2, median.len());
+        median.clear();
+        assert!(median.is_empty());
+    }
+
+    #[test]
+    fn test_rolling_median() {
+        let series = generate_series();
+        let medians = rolling_median(&series, 7);
+        assert_eq!(series.len(), medians.len());
+        for i in 0..series.len() {
+            let start = i.saturating_sub(3).min(series.len() - 7);
+            assert_eq!(naive(&series[start..start + 7], 0.5), medians[i]);
+        }
+    }
+
+    #[test]
+    fn test_rolling_median_short() {
+        assert_eq!(vec![2.0, 2.0, 2.0], rolling_median(&[1.0, 2.0, 3.0], 5));
+        assert!(rolling_median(&[], 5).is_empty());
+    }
+}
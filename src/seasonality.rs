@@ -0,0 +1,61 @@
+// Robust seasonal decomposition using medians
+// The trend is a centered moving median over one period, and the seasonal component
+// is the median of the detrended observations at each phase
+
+use crate::quantile::{median, moving_median};
+
+// Returns the seasonal component for each phase
+// The series must contain at least one period
+pub fn seasonal(z: &[f64], period: usize) -> Vec<f64> {
+    // estimate trend with a centered moving median
+    let detrended: Vec<f64> = z
+        .iter()
+        .zip(moving_median(z, period))
+        .map(|(v, m)| v - m)
+        .collect();
+
+    let mut component: Vec<f64> = (0..period)
+        .map(|p| {
+            let values: Vec<f64> = detrended.iter().skip(p).step_by(period).copied().collect();
+            median(&values)
+        })
+        .collect();
+
+    // center so the level of the series is unchanged
+    let center = median(&component);
+    for v in &mut component {
+        *v -= center;
+    }
+    component
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::preprocess::Preprocess;
+    use crate::seasonality::seasonal;
+
+    #[test]
+    fn test_seasonal() {
+        let pattern = [1.0, 5.0, 2.0, 0.0];
+        let series: Vec<f64> = (0..40).map(|i| 10.0 + pattern[i % 4]).collect();
+        let component = seasonal(&series, 4);
+        for i in 0..series.len() {
+            let adjusted = series[i] - component[i % 4];
+            assert!((adjusted - (series[0] - component[0])).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_deseasonalize() {
+        let pattern = [1.0, 5.0, 2.0, 0.0];
+        let series: Vec<f64> = (0..40).map(|i| 10.0 + pattern[i % 4]).collect();
+        let mut preprocess = Preprocess::new();
+        preprocess.seasonality = Some(4);
+        let prepared = preprocess.apply(&series, 5).unwrap();
+        for adjusted in [&prepared.z, &prepared.adjusted] {
+            for v in adjusted {
+                assert!((v - adjusted[0]).abs() < 1e-12);
+            }
+        }
+    }
+}
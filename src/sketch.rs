@@ -0,0 +1,257 @@
+// Fixed-memory quantile sketch over a bounded range
+// Like the interval trees in edm_tail, counts are stored in an implicit binary tree
+// where each node covers half of its parent's interval, but quantiles always descend
+// to a leaf, which bounds the error by the width of a leaf
+
+use crate::Error;
+
+/// An approximate quantile sketch for observations in a bounded range.
+///
+/// The range is divided into `2^depth` equal intervals, and memory is fixed by the depth.
+/// For observations inside the range, quantiles are within `(max - min) / 2^(depth + 1)`
+/// of the exact quantiles (with linear interpolation between order statistics).
+/// Observations outside the range are clamped to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantileSketch {
+    min: f64,
+    max: f64,
+    depth: u32,
+    counts: Vec<u64>,
+}
+
+impl QuantileSketch {
+    /// Creates a new sketch for a range.
+    pub fn new(min: f64, max: f64, depth: u32) -> Result<Self, Error> {
+        if !(min.is_finite() && max.is_finite() && min < max) {
+            return Err(Error::Parameter("max must be greater than min".to_string()));
+        }
+        if !(1..=20).contains(&depth) {
+            return Err(Error::Parameter(
+                "depth must be between 1 and 20".to_string(),
+            ));
+        }
+        Ok(Self {
+            min,
+            max,
+            depth,
+            counts: vec![0; 1 << (depth + 1)],
+        })
+    }
+
+    /// Returns the number of observations.
+    pub fn len(&self) -> u64 {
+        self.counts[1]
+    }
+
+    /// Returns whether there are no observations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum error of quantiles for observations inside the range.
+    pub fn error(&self) -> f64 {
+        self.width() / 2.0
+    }
+
+    /// Adds an observation.
+    pub fn add(&mut self, x: f64) {
+        let mut index = self.leaf(x);
+        while index != 0 {
+            self.counts[index] += 1;
+            index /= 2;
+        }
+    }
+
+    /// Removes an observation, returning whether its interval had any observations.
+    pub fn remove(&mut self, x: f64) -> bool {
+        let mut index = self.leaf(x);
+        if self.counts[index] == 0 {
+            return false;
+        }
+        while index != 0 {
+            self.counts[index] -= 1;
+            index /= 2;
+        }
+        true
+    }
+
+    /// Adds the observations of another sketch with the same range and depth.
+    pub fn merge(&mut self, other: &QuantileSketch) -> Result<(), Error> {
+        if self.min != other.min || self.max != other.max || self.depth != other.depth {
+            return Err(Error::Parameter(
+                "sketches must have the same range and depth".to_string(),
+            ));
+        }
+        for (c, o) in self.counts.iter_mut().zip(&other.counts) {
+            *c += o;
+        }
+        Ok(())
+    }
+
+    /// Returns the approximate quantile, or `None` when there are no observations.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        let h = (self.len() - 1) as f64 * q.clamp(0.0, 1.0);
+        let i = h.floor() as u64;
+        let lo = self.order_statistic(i);
+        if h.fract() == 0.0 {
+            Some(lo)
+        } else {
+            let hi = self.order_statistic(i + 1);
+            Some((1.0 - h.fract()) * lo + h.fract() * hi)
+        }
+    }
+
+    fn width(&self) -> f64 {
+        (self.max - self.min) / (1u64 << self.depth) as f64
+    }
+
+    // Returns the index of the leaf containing x
+    fn leaf(&self, x: f64) -> usize {
+        let leaves = 1usize << self.depth;
+        let position = ((x - self.min) / self.width()).floor();
+        // NaN is clamped to the first leaf
+        let offset = if position >= leaves as f64 {
+            leaves - 1
+        } else if position > 0.0 {
+            position as usize
+        } else {
+            0
+        };
+        leaves + offset
+    }
+
+    // Returns the midpoint of the leaf containing the observation with rank k (from 0)
+    fn order_statistic(&self, k: u64) -> f64 {
+        let leaves = 1usize << self.depth;
+        let mut k = k;
+        let mut i = 1;
+        while i < leaves {
+            let left = self.counts[2 * i];
+            if k < left {
+                i *= 2;
+            } else {
+                k -= left;
+                i = 2 * i + 1;
+            }
+        }
+        self.min + ((i - leaves) as f64 + 0.5) * self.width()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, QuantileSketch};
+
+    fn naive(x: &[f64], q: f64) -> f64 {
+        let mut x = x.to_vec();
+        x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let h = (x.len() - 1) as f64 * q;
+        let i = h.floor() as usize;
+        if i + 1 < x.len() {
+            x[i] + (h - i as f64) * (x[i + 1] - x[i])
+        } else {
+            x[i]
+        }
+    }
+
+    fn generate_series() -> Vec<f64> {
+        let mut seed: u64 = 1;
+        (0..1000)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 11) as f64 / (1u64 << 53) as f64 * 200.0 - 50.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_accuracy() {
+        let series = generate_series();
+        for depth in [4, 10] {
+            let mut sketch = QuantileSketch::new(-50.0, 150.0, depth).unwrap();
+            for v in &series {
+                sketch.add(*v);
+            }
+            assert_eq!(1000, sketch.len());
+            for q in [0.0, 0.01, 0.25, 0.5, 0.9, 0.99, 1.0] {
+                let error = (sketch.quantile(q).unwrap() - naive(&series, q)).abs();
+                assert!(error <= sketch.error());
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove() {
+        let series = generate_series();
+        let mut sketch = QuantileSketch::new(-50.0, 150.0, 12).unwrap();
+        for v in &series {
+            sketch.add(*v);
+        }
+        for v in &series[..500] {
+            assert!(sketch.remove(*v));
+        }
+        assert_eq!(500, sketch.len());
+        let error = (sketch.quantile(0.5).unwrap() - naive(&series[500..], 0.5)).abs();
+        assert!(error <= sketch.error());
+        assert!(!QuantileSketch::new(0.0, 1.0, 4).unwrap().remove(0.5));
+    }
+
+    #[test]
+    fn test_merge() {
+        let series = generate_series();
+        let mut first = QuantileSketch::new(-50.0, 150.0, 10).unwrap();
+        let mut second = first.clone();
+        let mut all = first.clone();
+        for (i, v) in series.iter().enumerate() {
+            if i % 2 == 0 {
+                first.add(*v);
+            } else {
+                second.add(*v);
+            }
+            all.add(*v);
+        }
+        first.merge(&second).unwrap();
+        assert_eq!(all, first);
+
+        let other = QuantileSketch::new(0.0, 1.0, 10).unwrap();
+        assert_eq!(
+            first.merge(&other).unwrap_err(),
+            Error::Parameter("sketches must have the same range and depth".to_string())
+        );
+    }
+
+    #[test]
+    fn test_clamp() {
+        let mut sketch = QuantileSketch::new(0.0, 1.0, 2).unwrap();
+        sketch.add(-5.0);
+        sketch.add(5.0);
+        assert_eq!(Some(0.125), sketch.quantile(0.0));
+        assert_eq!(Some(0.875), sketch.quantile(1.0));
+    }
+
+    #[test]
+    fn test_empty() {
+        let sketch = QuantileSketch::new(0.0, 1.0, 10).unwrap();
+        assert!(sketch.is_empty());
+        assert_eq!(None, sketch.quantile(0.5));
+    }
+
+    #[test]
+    fn test_bad_range() {
+        assert_eq!(
+            QuantileSketch::new(1.0, 1.0, 10).unwrap_err(),
+            Error::Parameter("max must be greater than min".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bad_depth() {
+        assert_eq!(
+            QuantileSketch::new(0.0, 1.0, 0).unwrap_err(),
+            Error::Parameter("depth must be between 1 and 20".to_string())
+        );
+    }
+}
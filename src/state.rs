@@ -0,0 +1,381 @@
+use crate::edm_multi::Program;
+use crate::statistic::with_divergence;
+use crate::{Error, Kernel, Statistic, Target};
+
+// identifies serialized states and their format
+const MAGIC: &[u8; 4] = b"BRKS";
+const VERSION: u8 = 1;
+
+/// The state of detecting multiple breakouts, which can be extended with new observations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiState {
+    pub(crate) min_size: usize,
+    pub(crate) degree: i32,
+    pub(crate) beta: f64,
+    pub(crate) percent: Option<f64>,
+    pub(crate) statistic: Statistic,
+    pub(crate) quantile: f64,
+    pub(crate) target: Target,
+    pub(crate) kernel: Kernel,
+    pub(crate) bandwidth: Option<f64>,
+    pub(crate) min: f64,
+    pub(crate) denom: f64,
+    // scaled observations
+    pub(crate) z: Vec<f64>,
+    pub(crate) program: Program,
+}
+
+impl MultiState {
+    /// Returns the breakouts.
+    pub fn breakouts(&self) -> Vec<usize> {
+        self.program.breakouts()
+    }
+
+    /// Returns the number of observations.
+    pub fn len(&self) -> usize {
+        self.z.len()
+    }
+
+    /// Returns whether there are no observations.
+    pub fn is_empty(&self) -> bool {
+        self.z.is_empty()
+    }
+
+    /// Appends observations and updates the breakouts.
+    ///
+    /// Only locations after the previous observations are computed. New observations are
+    /// scaled like the original ones, so breakouts can differ from fitting the whole series.
+    ///
+    /// The divergence is rebuilt from all observations and each new location is compared
+    /// with every earlier one, so each call takes at least linear time in the length of
+    /// the state. Append observations in batches when possible.
+    pub fn extend(&mut self, z: &[f64]) {
+        self.z.extend(z.iter().map(|x| (x - self.min) / self.denom));
+        self.update();
+    }
+
+    pub(crate) fn update(&mut self) {
+        let n = self.z.len();
+        let program = &mut self.program;
+        with_divergence!(self, &self.z, self.denom, |d| match self.percent {
+            Some(percent) => program.percent(n, &mut d, self.min_size, percent, self.degree, 0),
+            None => program.penalized(n, &mut d, self.min_size, self.beta, self.degree, 0),
+        });
+    }
+
+    /// Serializes the state.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::new());
+        w.0.extend_from_slice(MAGIC);
+        w.u8(VERSION);
+        w.u64(self.min_size as u64);
+        w.u64(self.degree as u64);
+        w.f64(self.beta);
+        w.option(self.percent);
+        w.u8(match self.statistic {
+            Statistic::Median => 0,
+            Statistic::Mean => 1,
+            Statistic::Kernel => 2,
+        });
+        w.f64(self.quantile);
+        w.u8(match self.target {
+            Target::Location => 0,
+            Target::Scale => 1,
+        });
+        w.u8(match self.kernel {
+            Kernel::Gaussian => 0,
+            Kernel::Laplace => 1,
+        });
+        w.option(self.bandwidth);
+        w.f64(self.min);
+        w.f64(self.denom);
+        w.u64(self.z.len() as u64);
+        for v in &self.z {
+            w.f64(*v);
+        }
+        for i in 0..self.program.f.len() {
+            w.u64(self.program.prev[i] as u64);
+            w.u64(self.program.number[i] as u64);
+            w.f64(self.program.f[i]);
+        }
+        w.0
+    }
+
+    /// Deserializes a state.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader(bytes);
+        if r.take(4)? != MAGIC || r.u8()? != VERSION {
+            return Err(invalid());
+        }
+        let min_size = r.u64()? as usize;
+        let degree = r.u64()? as i32;
+        let beta = r.f64()?;
+        let percent = r.option()?;
+        let statistic = match r.u8()? {
+            0 => Statistic::Median,
+            1 => Statistic::Mean,
+            2 => Statistic::Kernel,
+            _ => return Err(invalid()),
+        };
+        let quantile = r.f64()?;
+        let target = match r.u8()? {
+            0 => Target::Location,
+            1 => Target::Scale,
+            _ => return Err(invalid()),
+        };
+        let kernel = match r.u8()? {
+            0 => Kernel::Gaussian,
+            1 => Kernel::Laplace,
+            _ => return Err(invalid()),
+        };
+        let bandwidth = r.option()?;
+        let min = r.f64()?;
+        let denom = r.f64()?;
+
+        let n = r.u64()? as usize;
+        if n > r.0.len() / 8 {
+            return Err(invalid());
+        }
+        let z = (0..n).map(|_| r.f64()).collect::<Result<Vec<_>, _>>()?;
+
+        let mut program = Program::new();
+        for i in 0..n + 1 {
+            let prev = r.u64()? as usize;
+            // locations must point backward for the breakouts to be recovered
+            if prev > i || (prev == i && i != 0) {
+                return Err(invalid());
+            }
+            program.prev.push(prev);
+            program.number.push(r.u64()? as usize);
+            program.f.push(r.f64()?);
+        }
+        if !r.0.is_empty() {
+            return Err(invalid());
+        }
+
+        // same checks as the parameters
+        if min_size < 2
+            || !(0..=2).contains(&degree)
+            || !beta.is_finite()
+            || !percent.map_or(true, f64::is_finite)
+            || !(0.0..=1.0).contains(&quantile)
+            || !bandwidth.map_or(true, |b| b.is_finite() && b > 0.0)
+            || !min.is_finite()
+            || !(denom.is_finite() && denom > 0.0)
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            min_size,
+            degree,
+            beta,
+            percent,
+            statistic,
+            quantile,
+            target,
+            kernel,
+            bandwidth,
+            min,
+            denom,
+            z,
+            program,
+        })
+    }
+}
+
+fn invalid() -> Error {
+    Error::State("invalid state".to_string())
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn option(&mut self, v: Option<f64>) {
+        match v {
+            Some(v) => {
+                self.u8(1);
+                self.f64(v);
+            }
+            None => self.u8(0),
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < n {
+            return Err(invalid());
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, Error> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn option(&mut self) -> Result<Option<f64>, Error> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.f64()?)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Error, MultiState, Statistic};
+
+    #[rustfmt::skip]
+    fn generate_series() -> Vec<f64> {
+        vec![
+            3.0, 1.0, 2.0, 3.0, 2.0, 1.0, 1.0, 2.0, 2.0, 3.0,
+            6.0, 4.0, 4.0, 5.0, 6.0, 4.0, 4.0, 4.0, 6.0, 5.0,
+            9.0, 8.0, 7.0, 9.0, 8.0, 9.0, 9.0, 9.0, 7.0, 9.0
+        ]
+    }
+
+    #[test]
+    fn test_extend() {
+        let series = generate_series();
+        let state = crate::multi().min_size(5).fit_state(&series).unwrap();
+        assert_eq!(vec![10, 15, 20], state.breakouts());
+
+        // the prefix has the same scale as the whole series
+        let mut state = crate::multi().min_size(5).fit_state(&series[..21]).unwrap();
+        state.extend(&series[21..]);
+        assert_eq!(30, state.len());
+        assert_eq!(vec![10, 15, 20], state.breakouts());
+    }
+
+    #[test]
+    fn test_extend_parts() {
+        let series = generate_series();
+        for statistic in [Statistic::Median, Statistic::Mean, Statistic::Kernel] {
+            let mut params = crate::multi();
+            params.min_size(5).statistic(statistic);
+
+            let mut state = params.fit_state(&series[..12]).unwrap();
+            state.extend(&series[12..]);
+
+            let mut parts = params.fit_state(&series[..12]).unwrap();
+            parts.extend(&series[12..17]);
+            parts.extend(&series[17..]);
+            assert_eq!(state, parts);
+        }
+    }
+
+    #[test]
+    fn test_percent() {
+        let series = generate_series();
+        let expected = crate::multi()
+            .min_size(5)
+            .percent(0.5)
+            .fit(&series)
+            .unwrap();
+        let mut state = crate::multi()
+            .min_size(5)
+            .percent(0.5)
+            .fit_state(&series[..25])
+            .unwrap();
+        state.extend(&series[25..]);
+        assert_eq!(expected, state.breakouts());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let series = generate_series();
+        let state = crate::multi()
+            .min_size(5)
+            .statistic(Statistic::Kernel)
+            .fit_state(&series)
+            .unwrap();
+        let bytes = state.to_bytes();
+        assert_eq!(state, MultiState::from_bytes(&bytes).unwrap());
+
+        let result = MultiState::from_bytes(&bytes[..bytes.len() - 1]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::State("invalid state".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bytes_invalid() {
+        let series = generate_series();
+        let state = crate::multi()
+            .min_size(5)
+            .statistic(Statistic::Kernel)
+            .fit_state(&series)
+            .unwrap();
+        let changes: [fn(&mut MultiState); 6] = [
+            |s| s.quantile = 1.5,
+            |s| s.quantile = f64::NAN,
+            |s| s.beta = f64::INFINITY,
+            |s| s.percent = Some(f64::NAN),
+            |s| s.bandwidth = Some(0.0),
+            |s| s.bandwidth = Some(f64::NAN),
+        ];
+        for change in changes {
+            let mut invalid = state.clone();
+            change(&mut invalid);
+            let result = MultiState::from_bytes(&invalid.to_bytes());
+            assert_eq!(
+                result.unwrap_err(),
+                Error::State("invalid state".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_constant() {
+        let mut state = crate::multi().min_size(5).fit_state(&[1.0; 10]).unwrap();
+        assert!(state.breakouts().is_empty());
+        state.extend(&[5.0; 10]);
+        assert_eq!(vec![10], state.breakouts());
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut state = crate::multi().min_size(5).fit_state(&[]).unwrap();
+        assert!(state.is_empty());
+        assert!(state.breakouts().is_empty());
+        state.extend(&generate_series());
+        assert_eq!(30, state.len());
+        assert_eq!(vec![10, 15, 20], state.breakouts());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let result = crate::multi().refine(true).fit_state(&generate_series());
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("refine cannot be used with state".to_string())
+        );
+    }
+}
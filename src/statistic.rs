@@ -0,0 +1,56 @@
+/// The statistic used to compare segments.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Statistic {
+    /// Robust to outliers.
+    Median,
+    /// Faster, but sensitive to outliers.
+    Mean,
+    /// Detects changes in the whole distribution.
+    Kernel,
+}
+
+// Evaluates the body with the divergence for the statistic and target of a scaled series
+// bound to d, so the body is compiled for each divergence and dispatched statically
+// Params are a place with statistic, target, quantile, kernel, and bandwidth fields
+macro_rules! with_divergence {
+    ($params:expr, $z:expr, $denom:expr, |$d:ident| $body:expr) => {{
+        let z = $z;
+        match ($params.target, $params.statistic) {
+            (_, $crate::Statistic::Kernel) => {
+                #[allow(unused_mut)]
+                let mut $d = $crate::kernel::features(z, $params.kernel, $params.bandwidth, $denom);
+                $body
+            }
+            ($crate::Target::Location, $crate::Statistic::Median) => {
+                #[allow(unused_mut)]
+                let mut $d = $crate::divergence::Trackers::new(
+                    z,
+                    $crate::ranks::RankQuantile::new(z, $params.quantile),
+                    $crate::ranks::RankQuantile::new(z, $params.quantile),
+                );
+                $body
+            }
+            ($crate::Target::Location, $crate::Statistic::Mean) => {
+                #[allow(unused_mut)]
+                let mut $d = $crate::divergence::Means::new(z);
+                $body
+            }
+            ($crate::Target::Scale, $crate::Statistic::Median) => {
+                #[allow(unused_mut)]
+                let mut $d = $crate::divergence::Trackers::new(
+                    z,
+                    $crate::ranks::RankInterquartileRange::new(z),
+                    $crate::ranks::RankInterquartileRange::new(z),
+                );
+                $body
+            }
+            ($crate::Target::Scale, $crate::Statistic::Mean) => {
+                #[allow(unused_mut)]
+                let mut $d = $crate::divergence::Deviations::new(z);
+                $body
+            }
+        }
+    }};
+}
+
+pub(crate) use with_divergence;
@@ -0,0 +1,8 @@
+/// The property of the series to detect changes in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Target {
+    /// Changes in level.
+    Location,
+    /// Changes in spread (interquartile range or standard deviation).
+    Scale,
+}
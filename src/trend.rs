@@ -0,0 +1,89 @@
+use crate::quantile::median;
+
+/// A method for estimating a linear trend.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Trend {
+    /// Least squares.
+    Linear,
+    /// Median of pairwise slopes, which is robust to outliers and level shifts.
+    TheilSen,
+}
+
+// maximum number of observations used for pairwise slopes
+const THEIL_SEN_SIZE: usize = 1000;
+
+impl Trend {
+    // Returns the slope per observation
+    pub(crate) fn slope(&self, z: &[f64]) -> f64 {
+        match self {
+            Trend::Linear => least_squares(z),
+            Trend::TheilSen => theil_sen(z),
+        }
+    }
+}
+
+fn least_squares(z: &[f64]) -> f64 {
+    let n = z.len() as f64;
+    let x_mean = (n - 1.0) / 2.0;
+    let y_mean = z.iter().sum::<f64>() / n;
+    let mut num = 0.0;
+    let mut den = 0.0;
+    for (i, v) in z.iter().enumerate() {
+        let dx = i as f64 - x_mean;
+        num += dx * (v - y_mean);
+        den += dx * dx;
+    }
+    if den == 0.0 {
+        0.0
+    } else {
+        num / den
+    }
+}
+
+// Uses evenly spaced observations for long series
+fn theil_sen(z: &[f64]) -> f64 {
+    let step = ((z.len() + THEIL_SEN_SIZE - 1) / THEIL_SEN_SIZE).max(1);
+    let mut slopes = Vec::new();
+    for i in (0..z.len()).step_by(step) {
+        for j in (i + step..z.len()).step_by(step) {
+            slopes.push((z[j] - z[i]) / (j - i) as f64);
+        }
+    }
+    if slopes.is_empty() {
+        0.0
+    } else {
+        median(&slopes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::preprocess::Preprocess;
+    use crate::Trend;
+
+    #[test]
+    fn test_slope() {
+        let series: Vec<f64> = (0..20).map(|i| 1.0 + 0.5 * i as f64).collect();
+        assert!((Trend::Linear.slope(&series) - 0.5).abs() < 1e-12);
+        assert!((Trend::TheilSen.slope(&series) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_theil_sen_robust() {
+        let mut series: Vec<f64> = (0..20).map(|i| 0.5 * i as f64).collect();
+        series[3] = 100.0;
+        assert!((Trend::TheilSen.slope(&series) - 0.5).abs() < 1e-12);
+        assert!((Trend::Linear.slope(&series) - 0.5).abs() > 0.1);
+    }
+
+    #[test]
+    fn test_detrend() {
+        let series: Vec<f64> = (0..20).map(|i| 3.0 + 2.0 * i as f64).collect();
+        let mut preprocess = Preprocess::new();
+        preprocess.detrend = Some(Trend::Linear);
+        let prepared = preprocess.apply(&series, 5).unwrap();
+        for v in prepared.z.iter().chain(&prepared.adjusted) {
+            assert!((v - 3.0).abs() < 1e-9);
+        }
+    }
+}
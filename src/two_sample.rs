@@ -0,0 +1,209 @@
+// Energy distance test for comparing two samples
+// The E-statistic compares distances between and within the samples, and the p-value
+// is the share of random splits of the pooled observations with a larger statistic
+
+use crate::bootstrap::Rng;
+use crate::Error;
+
+/// Parameters for comparing two samples.
+pub struct TwoSampleParams {
+    alpha: f64,
+    permutations: usize,
+    seed: u64,
+}
+
+/// Returns parameters for comparing two samples.
+pub fn two_sample() -> TwoSampleParams {
+    TwoSampleParams {
+        alpha: 1.0,
+        permutations: 199,
+        seed: 0,
+    }
+}
+
+/// The result of comparing two samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TwoSample {
+    statistic: f64,
+    p_value: f64,
+}
+
+impl TwoSample {
+    /// Returns the E-statistic, which is zero when the samples have the same distribution.
+    pub fn statistic(&self) -> f64 {
+        self.statistic
+    }
+
+    /// Returns the permutation p-value.
+    pub fn p_value(&self) -> f64 {
+        self.p_value
+    }
+}
+
+impl TwoSampleParams {
+    /// Sets the weight of the distance between observations, greater than 0 and at most 2.
+    pub fn alpha(&mut self, value: f64) -> &mut Self {
+        self.alpha = value;
+        self
+    }
+
+    /// Sets the number of permutations for the p-value.
+    pub fn permutations(&mut self, value: usize) -> &mut Self {
+        self.permutations = value;
+        self
+    }
+
+    /// Sets the seed for permutations.
+    pub fn seed(&mut self, value: u64) -> &mut Self {
+        self.seed = value;
+        self
+    }
+
+    /// Compares two samples.
+    pub fn test(&self, a: &[f64], b: &[f64]) -> Result<TwoSample, Error> {
+        if self.alpha <= 0.0 || self.alpha > 2.0 {
+            return Err(Error::Parameter(
+                "alpha must be greater than 0 and at most 2".to_string(),
+            ));
+        }
+        if self.permutations == 0 {
+            return Err(Error::Parameter(
+                "permutations must be positive".to_string(),
+            ));
+        }
+        if a.is_empty() || b.is_empty() {
+            return Err(Error::Parameter("samples cannot be empty".to_string()));
+        }
+        if a.iter().chain(b).any(|v| !v.is_finite()) {
+            return Err(Error::Parameter("samples must be finite".to_string()));
+        }
+
+        let pooled: Vec<f64> = a.iter().chain(b).copied().collect();
+        let distances = Distances {
+            z: &pooled,
+            alpha: self.alpha,
+        };
+        let mut order: Vec<usize> = (0..pooled.len()).collect();
+        let statistic = distances.statistic(&order, a.len());
+
+        // shuffle with Fisher-Yates
+        let mut rng = Rng::new(self.seed);
+        let mut larger = 0;
+        for _ in 0..self.permutations {
+            for i in (1..order.len()).rev() {
+                order.swap(i, rng.below(i + 1));
+            }
+            if distances.statistic(&order, a.len()) >= statistic {
+                larger += 1;
+            }
+        }
+
+        Ok(TwoSample {
+            statistic,
+            p_value: (larger + 1) as f64 / (self.permutations + 1) as f64,
+        })
+    }
+}
+
+// Pairwise distances of the pooled observations, raised to alpha
+// Distances are computed for each permutation instead of stored, so memory is linear
+struct Distances<'a> {
+    z: &'a [f64],
+    alpha: f64,
+}
+
+impl Distances<'_> {
+    // E-statistic when the first m observations in order are the first sample
+    fn statistic(&self, order: &[usize], m: usize) -> f64 {
+        let (x, y) = order.split_at(m);
+        let within_x = self.sum(x, x) / (m * m) as f64;
+        let within_y = self.sum(y, y) / (y.len() * y.len()) as f64;
+        let between = self.sum(x, y) / (m * y.len()) as f64;
+        let scale = (m * y.len()) as f64 / self.z.len() as f64;
+        scale * (2.0 * between - within_x - within_y)
+    }
+
+    fn sum(&self, x: &[usize], y: &[usize]) -> f64 {
+        let mut total = 0.0;
+        for i in x {
+            let zi = self.z[*i];
+            for j in y {
+                total += (zi - self.z[*j]).abs().powf(self.alpha);
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Error;
+
+    #[test]
+    fn test_statistic() {
+        let result = crate::two_sample().test(&[0.0], &[1.0]).unwrap();
+        assert_eq!(1.0, result.statistic());
+
+        // mean distance between is 2, and within is 2 / 3 for a
+        let result = crate::two_sample().test(&[0.0, 1.0, 2.0], &[3.0]).unwrap();
+        assert!((result.statistic() - 0.75 * (4.0 - 8.0 / 9.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_different() {
+        let a: Vec<f64> = (0..30).map(|i| (i % 5) as f64).collect();
+        let b: Vec<f64> = (0..30).map(|i| (i % 5) as f64 + 3.0).collect();
+        let result = crate::two_sample().test(&a, &b).unwrap();
+        assert_eq!(0.005, result.p_value());
+    }
+
+    #[test]
+    fn test_same() {
+        let a: Vec<f64> = (0..30).map(|i| (i % 5) as f64).collect();
+        let result = crate::two_sample().test(&a, &a).unwrap();
+        assert_eq!(0.0, result.statistic());
+        assert_eq!(1.0, result.p_value());
+    }
+
+    #[test]
+    fn test_seed() {
+        let a = [1.0, 3.0, 2.0, 5.0, 4.0];
+        let b = [2.0, 4.0, 6.0, 3.0, 7.0];
+        let first = crate::two_sample().seed(1).test(&a, &b).unwrap();
+        let second = crate::two_sample().seed(1).test(&a, &b).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_bad_alpha() {
+        let result = crate::two_sample().alpha(3.0).test(&[1.0], &[2.0]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("alpha must be greater than 0 and at most 2".to_string())
+        );
+
+        let result = crate::two_sample().alpha(0.0).test(&[1.0], &[2.0]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("alpha must be greater than 0 and at most 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_not_finite() {
+        let result = crate::two_sample().test(&[1.0, f64::NAN], &[2.0]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("samples must be finite".to_string())
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let result = crate::two_sample().test(&[], &[2.0]);
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Parameter("samples cannot be empty".to_string())
+        );
+    }
+}